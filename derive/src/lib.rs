@@ -0,0 +1,315 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Expr, Fields, GenericArgument, PathArguments, Type};
+
+mod syntax;
+
+#[proc_macro]
+pub fn seq(input : proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let seq = parse_macro_input!(input as syntax::Seq);
+    syntax::expand_seq(seq).into()
+}
+
+#[proc_macro]
+pub fn alt(input : proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let alt = parse_macro_input!(input as syntax::Alt);
+    syntax::expand_alt(alt).into()
+}
+
+#[proc_macro_derive(Pattern, attributes(pattern))]
+pub fn derive_pattern(input : proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    literal : Option<Expr>,
+    cond : Option<Expr>,
+    count : Option<Expr>,
+    with : Option<Expr>,
+}
+
+fn field_attrs(attrs : &[syn::Attribute]) -> syn::Result<FieldAttrs> {
+    let mut ret = FieldAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("pattern")) {
+        attr.parse_nested_meta(|meta| {
+            let slot = if meta.path.is_ident("literal") {
+                &mut ret.literal
+            }
+            else if meta.path.is_ident("cond") {
+                &mut ret.cond
+            }
+            else if meta.path.is_ident("count") {
+                &mut ret.count
+            }
+            else if meta.path.is_ident("with") {
+                &mut ret.with
+            }
+            else {
+                return Err(meta.error("expected `literal`, `cond`, `count` or `with`"));
+            };
+            if slot.is_some() {
+                return Err(meta.error("duplicate attribute"));
+            }
+            *slot = Some(meta.value()?.parse()?);
+            Ok(())
+        })?;
+    }
+    Ok(ret)
+}
+
+#[derive(Default)]
+struct ContainerAttrs {
+    tag : Option<Type>,
+    bytes : bool,
+}
+
+fn container_attrs(attrs : &[syn::Attribute]) -> syn::Result<ContainerAttrs> {
+    let mut ret = ContainerAttrs::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("pattern")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("bytes") {
+                ret.bytes = true;
+                return Ok(());
+            }
+            if !meta.path.is_ident("tag") {
+                return Err(meta.error("expected `tag` or `bytes`"));
+            }
+            if ret.tag.is_some() {
+                return Err(meta.error("duplicate attribute"));
+            }
+            ret.tag = Some(meta.value()?.parse()?);
+            Ok(())
+        })?;
+    }
+    Ok(ret)
+}
+
+fn variant_tag(attrs : &[syn::Attribute]) -> syn::Result<Option<Expr>> {
+    let mut ret = None;
+    for attr in attrs.iter().filter(|a| a.path().is_ident("pattern")) {
+        attr.parse_nested_meta(|meta| {
+            if !meta.path.is_ident("tag") {
+                return Err(meta.error("expected `tag`"));
+            }
+            if ret.is_some() {
+                return Err(meta.error("duplicate attribute"));
+            }
+            ret = Some(meta.value()?.parse()?);
+            Ok(())
+        })?;
+    }
+    Ok(ret)
+}
+
+fn inner_type<'t>(ty : &'t Type, wrapper : &str, attr : &str) -> syn::Result<&'t Type> {
+    if let Type::Path(p) = ty {
+        if let Some(segment) = p.path.segments.last() {
+            if segment.ident == wrapper {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let Some(GenericArgument::Type(inner)) = args.args.first() {
+                        return Ok(inner);
+                    }
+                }
+            }
+        }
+    }
+    Err(Error::new(ty.span(), format!("`{}` requires a field of type `{}<_>`", attr, wrapper)))
+}
+
+struct Tag<'a> {
+    ty : &'a Type,
+    value : Expr,
+}
+
+fn fields_body(fields : &Fields, ctor : TokenStream, tag : Option<Tag<'_>>, bounds : &mut Vec<Type>, borrow : &mut bool) -> syn::Result<TokenStream> {
+    let mut steps = vec![];
+    let mut position = 0;
+
+    if let Some(Tag { ty, value }) = tag {
+        bounds.push(ty.clone());
+        let read = quote! { <#ty as ::array_pattern::Pattern<__T>>::pattern(input) };
+        steps.push(step(position, format_ident!("__tag"), ty, read, Some(value)));
+        position += 1;
+    }
+
+    let mut names = vec![];
+    for (k, field) in fields.iter().enumerate() {
+        let attrs = field_attrs(&field.attrs)?;
+        let name = match &field.ident {
+            Some(ident) => ident.clone(),
+            None => format_ident!("__f{}", k),
+        };
+        let ty = &field.ty;
+
+        let mut inner = ty;
+        if attrs.cond.is_some() {
+            inner = inner_type(inner, "Option", "cond")?;
+        }
+        let element = match attrs.count {
+            Some(_) => inner_type(inner, "Vec", "count")?,
+            None => inner,
+        };
+        let matcher = match &attrs.with {
+            Some(with) => {
+                *borrow = true;
+                quote_spanned! { with.span() => |input| #with(input) }
+            },
+            None => {
+                bounds.push(element.clone());
+                quote! { |input| <#element as ::array_pattern::Pattern<__T>>::pattern(input) }
+            },
+        };
+        let mut read = match &attrs.count {
+            Some(count) => quote! { ::array_pattern::derive_repeat(input, (#count) as usize, #matcher) },
+            None => quote! { (#matcher)(input) },
+        };
+        if let Some(cond) = &attrs.cond {
+            read = quote! {
+                if #cond {
+                    (#read).map(|s| ::array_pattern::Success { item: Some(s.item), start: s.start, end: s.end })
+                }
+                else {
+                    Ok(::array_pattern::Success { item: None, start: __end, end: __end })
+                }
+            };
+        }
+
+        steps.push(step(position, name.clone(), ty, read, attrs.literal));
+        position += 1;
+        names.push(name);
+    }
+
+    let item = match fields {
+        Fields::Named(_) => quote! { #ctor { #(#names),* } },
+        Fields::Unnamed(_) => quote! { #ctor ( #(#names),* ) },
+        Fields::Unit => quote! { #ctor },
+    };
+
+    Ok(quote! {
+        let __rp = ::array_pattern::MatchInput::checkpoint(input);
+        let mut __start : usize = 0;
+        let mut __end : usize = 0;
+        #(#steps)*
+        Ok(::array_pattern::Success { item: #item, start: __start, end: __end })
+    })
+}
+
+fn step(position : usize, name : syn::Ident, ty : &Type, read : TokenStream, literal : Option<Expr>) -> TokenStream {
+    let fatal = position != 0;
+    let span = if fatal {
+        quote! {
+            if __end < __s.end {
+                __end = __s.end;
+            }
+        }
+    }
+    else {
+        quote! {
+            __start = __s.start;
+            __end = __s.end;
+        }
+    };
+    let check = literal.map(|literal| {
+        let e = if fatal { quote! { Fatal } } else { quote! { Error } };
+        quote! {
+            if __s.item != (#literal) {
+                ::array_pattern::reset(input, __rp);
+                return Err(::array_pattern::MatchError::#e(__s.start));
+            }
+        }
+    });
+    quote! {
+        let __s : ::array_pattern::Success<#ty> = match #read {
+            Ok(s) => s,
+            Err(e) => return Err(::array_pattern::derive_error(input, __rp, #fatal, e)),
+        };
+        #span
+        #check
+        let #name : #ty = __s.item;
+    }
+}
+
+fn expand(input : DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let kind = name.to_string();
+    let mut bounds = vec![];
+    let mut borrow = false;
+
+    let container = container_attrs(&input.attrs)?;
+
+    let body = match &input.data {
+        Data::Struct(data) => {
+            if let Some(tag) = &container.tag {
+                return Err(Error::new(tag.span(), "`tag` is only supported on enums"));
+            }
+            let body = fields_body(&data.fields, quote! { Self }, None, &mut bounds, &mut borrow)?;
+            quote! {
+                ::array_pattern::node(input, #kind, |input| { #body })
+            }
+        },
+        Data::Enum(data) => {
+            if data.variants.is_empty() {
+                return Err(Error::new(name.span(), "`Pattern` cannot be derived for empty enums"));
+            }
+            let tag_ty = &container.tag;
+            let mut variants = vec![];
+            for variant in &data.variants {
+                let ident = &variant.ident;
+                let tag = match (variant_tag(&variant.attrs)?, tag_ty) {
+                    (Some(value), Some(ty)) => Some(Tag { ty, value }),
+                    (Some(value), None) => return Err(Error::new(value.span(), "variant `tag` requires `#[pattern(tag = Type)]` on the enum")),
+                    (None, _) => None,
+                };
+                let body = fields_body(&variant.fields, quote! { Self::#ident }, tag, &mut bounds, &mut borrow)?;
+                let variant_kind = ident.to_string();
+                variants.push(quote! {
+                    match ::array_pattern::node(input, #variant_kind, |input| { #body }) {
+                        Ok(s) => return Ok(s),
+                        Err(e @ ::array_pattern::MatchError::Error(_)) => { __error = Some(e); },
+                        Err(e @ ::array_pattern::MatchError::ErrorEndOfFile) => { __error = Some(e); },
+                        Err(e) => return Err(e),
+                    }
+                });
+            }
+            quote! {
+                ::array_pattern::node(input, #kind, |input| {
+                    let mut __error : Option<::array_pattern::MatchError> = None;
+                    #(#variants)*
+                    Err(__error.unwrap())
+                })
+            }
+        },
+        Data::Union(data) => {
+            return Err(Error::new(data.union_token.span(), "`Pattern` cannot be derived for unions"));
+        },
+    };
+
+    let mut generics = input.generics.clone();
+    generics.params.insert(input.generics.lifetimes().count(), parse_quote! { __T });
+    {
+        let where_clause = generics.make_where_clause();
+        if container.bytes || borrow {
+            where_clause.predicates.push(parse_quote! { __T : ::std::borrow::Borrow<u8> });
+        }
+        for ty in &bounds {
+            where_clause.predicates.push(parse_quote! { #ty : ::array_pattern::Pattern<__T> });
+        }
+    }
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::array_pattern::Pattern<__T> for #name #ty_generics #where_clause {
+            #[allow(unused_mut, unused_variables, clippy::all)]
+            fn pattern(input : &mut impl ::array_pattern::MatchInput<Item = __T>) -> ::core::result::Result<::array_pattern::Success<Self>, ::array_pattern::MatchError> {
+                #body
+            }
+        }
+    })
+}
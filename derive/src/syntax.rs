@@ -0,0 +1,365 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{braced, parenthesized, token, Block, Error, Ident, Lifetime, Pat, Path, Token, Type};
+
+enum Mode {
+    Once,
+    ZeroOrMore,
+    Maybe,
+}
+
+struct Header {
+    mode : Mode,
+    skip : Option<Path>,
+    name : Ident,
+    life : Lifetime,
+    in_t : Type,
+    out_t : Type,
+}
+
+fn parse_header(input : ParseStream, allow_mode : bool) -> syn::Result<Header> {
+    let mut mode = Mode::Once;
+    if input.peek(Ident) && input.peek2(Token![~]) {
+        let ident : Ident = input.fork().parse()?;
+        if ident == "zero_or_more" || ident == "maybe" {
+            if !allow_mode {
+                return Err(Error::new(ident.span(), format!("`{}` is only supported by `seq!`", ident)));
+            }
+            input.parse::<Ident>()?;
+            input.parse::<Token![~]>()?;
+            mode = if ident == "maybe" { Mode::Maybe } else { Mode::ZeroOrMore };
+        }
+    }
+
+    let mut skip = None;
+    if input.peek(Ident) && input.peek2(token::Paren) {
+        let ident : Ident = input.parse()?;
+        if ident != "skip" {
+            return Err(Error::new(ident.span(), "expected `skip(...)` or a rule name"));
+        }
+        let content;
+        parenthesized!(content in input);
+        skip = Some(content.parse()?);
+        input.parse::<Token![~]>().map_err(|e| Error::new(e.span(), "expected `~` after `skip(...)`"))?;
+    }
+
+    let name : Ident = input.parse().map_err(|e| Error::new(e.span(), "expected a rule name"))?;
+    input.parse::<Token![<]>().map_err(|e| Error::new(e.span(), "expected `<'a>` after the rule name"))?;
+    let life : Lifetime = input.parse()?;
+    input.parse::<Token![>]>()?;
+    input.parse::<Token![:]>().map_err(|e| Error::new(e.span(), "expected `: InputType => OutputType =` after the rule name"))?;
+    let in_t : Type = input.parse()?;
+    input.parse::<Token![=>]>().map_err(|e| Error::new(e.span(), "expected `=>` between the input and output types"))?;
+    let out_t : Type = input.parse()?;
+    input.parse::<Token![=]>().map_err(|e| Error::new(e.span(), "expected `=` after the output type"))?;
+
+    Ok(Header { mode, skip, name, life, in_t, out_t })
+}
+
+enum Item {
+    Matcher(Ident, Ident),
+    Pattern(Ident, Pat),
+}
+
+pub struct Seq {
+    header : Header,
+    items : Vec<Item>,
+    action : Block,
+}
+
+impl Parse for Seq {
+    fn parse(input : ParseStream) -> syn::Result<Self> {
+        let header = parse_header(input, true)?;
+        let mut items = vec![];
+        loop {
+            if input.peek(token::Brace) {
+                let content;
+                let brace = braced!(content in input);
+                let stmts = content.call(Block::parse_within)?;
+                let action = Block { brace_token: brace, stmts };
+                if !input.is_empty() {
+                    return Err(input.error("unexpected tokens after the action block; the `{ ... }` block must be the last item"));
+                }
+                return Ok(Seq { header, items, action });
+            }
+            if input.is_empty() {
+                return Err(Error::new(Span::call_site(), "missing action block; a sequence must end with `{ ... }`"));
+            }
+
+            let name : Ident = input.parse().map_err(|e| Error::new(e.span(), "expected `name <= matcher` or a `{ ... }` action block"))?;
+            if !input.peek(Token![<=]) {
+                return Err(input.error(format!("expected `<=` after `{}`", name)));
+            }
+            input.parse::<Token![<=]>()?;
+            let pat = Pat::parse_multi(input)?;
+            let span = pat.span();
+
+            let item = match pat {
+                Pat::Ident(ref p) if p.by_ref.is_none() && p.mutability.is_none() && p.subpat.is_none() => Item::Matcher(name, p.ident.clone()),
+                pat => Item::Pattern(name, pat),
+            };
+            items.push(item);
+
+            if input.is_empty() {
+                return Err(Error::new(span, "missing action block; expected `, { ... }` after the last item"));
+            }
+            input.parse::<Token![,]>().map_err(|e| Error::new(e.span(), "expected `,` after the item"))?;
+        }
+    }
+}
+
+pub struct Alt {
+    header : Header,
+    matchers : Vec<Ident>,
+}
+
+impl Parse for Alt {
+    fn parse(input : ParseStream) -> syn::Result<Self> {
+        let header = parse_header(input, false)?;
+        let mut matchers = vec![input.parse::<Ident>().map_err(|e| Error::new(e.span(), "expected a matcher name"))?];
+        while !input.is_empty() {
+            input.parse::<Token![|]>().map_err(|e| Error::new(e.span(), "expected `|` between alternatives"))?;
+            matchers.push(input.parse::<Ident>().map_err(|e| Error::new(e.span(), "expected a matcher name"))?);
+        }
+        Ok(Alt { header, matchers })
+    }
+}
+
+fn skip_path(header : &Header) -> TokenStream {
+    match &header.skip {
+        Some(path) => quote! { #path },
+        None => quote! { ::array_pattern::no_trivia },
+    }
+}
+
+fn trivia(skip : &TokenStream) -> TokenStream {
+    quote! {
+        match #skip(input) {
+            Ok(_) | Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => { },
+            Err(MatchError::Incomplete(n)) => {
+                ::array_pattern::reset(input, _rp);
+                return Err(MatchError::Incomplete(n));
+            },
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Mirrors the `seq!` and `alt!` arms in src/lib.rs; `array_pattern::syntax` tests check that both expansions agree.
+fn seq_body(seq : &Seq, skip : &TokenStream) -> TokenStream {
+    let mut steps = vec![];
+    for (k, item) in seq.items.iter().enumerate() {
+        let first = k == 0;
+        let step = match item {
+            Item::Matcher(n, m) if first => quote_spanned! { m.span() =>
+                let v = #m(input)?;
+                let #n = v.item;
+                _start = v.start;
+                _end = v.end;
+            },
+            Item::Matcher(n, m) => {
+                let trivia = trivia(skip);
+                quote_spanned! { m.span() =>
+                    #trivia
+                    let #n = match #m(input) {
+                        Ok(v) => {
+                            if _end < v.end {
+                                _end = v.end;
+                            }
+                            v.item
+                        },
+                        Err(MatchError::Error(i)) => return Err(MatchError::Fatal(i)),
+                        Err(MatchError::ErrorEndOfFile) => return Err(MatchError::FatalEndOfFile),
+                        Err(MatchError::Incomplete(n)) => {
+                            ::array_pattern::reset(input, _rp);
+                            return Err(MatchError::Incomplete(n));
+                        },
+                        Err(e) => return Err(e),
+                    };
+                }
+            },
+            Item::Pattern(n, p) if first => quote_spanned! { p.span() =>
+                #[allow(unreachable_patterns, unused_parens, clippy::redundant_pattern)]
+                let #n = match input.next_item() {
+                    Some((i, item @ (#p))) => {
+                        _start = i;
+                        _end = i;
+                        item
+                    },
+                    Some((i, _)) => {
+                        ::array_pattern::reset(input, _rp);
+                        return Err(MatchError::Error(i));
+                    },
+                    _ => {
+                        let e = input.end_of_input(false);
+                        ::array_pattern::reset(input, _rp);
+                        return Err(e);
+                    },
+                };
+            },
+            Item::Pattern(n, p) => {
+                let trivia = trivia(skip);
+                quote_spanned! { p.span() =>
+                    #trivia
+                    #[allow(unreachable_patterns, unused_parens, clippy::redundant_pattern)]
+                    let #n = match input.next_item() {
+                        Some((i, item @ (#p))) => {
+                            _end = i;
+                            item
+                        },
+                        Some((i, _)) => {
+                            ::array_pattern::reset(input, _rp);
+                            return Err(MatchError::Fatal(i));
+                        },
+                        _ => {
+                            let e = input.end_of_input(true);
+                            ::array_pattern::reset(input, _rp);
+                            return Err(e);
+                        },
+                    };
+                }
+            },
+        };
+        steps.push(step);
+    }
+    let action = &seq.action;
+    quote! {
+        let _rp = input.checkpoint();
+        let mut _start : usize = 0;
+        let mut _end : usize = 0;
+        #(#steps)*
+        #[allow(clippy::unused_unit)]
+        let item = #action;
+        return Ok(Success { start: _start, end: _end, item });
+    }
+}
+
+pub fn expand_seq(seq : Seq) -> TokenStream {
+    let Header { mode, name, life, in_t, out_t, .. } = &seq.header;
+    let kind = name.to_string();
+    let skip = skip_path(&seq.header);
+    let body = seq_body(&seq, &skip);
+
+    match mode {
+        Mode::Once => quote! {
+            #[allow(clippy::extra_unused_lifetimes, clippy::let_unit_value)]
+            fn #name<#life>(input : &mut impl MatchInput<Item = #in_t>) -> Result<Success<#out_t>, MatchError> {
+                ::array_pattern::node(input, #kind, |input| {
+                    #body
+                })
+            }
+        },
+        Mode::ZeroOrMore => quote! {
+            #[allow(clippy::extra_unused_lifetimes, clippy::let_unit_value)]
+            fn #name<#life>(input : &mut impl MatchInput<Item = #in_t>) -> Result<Success<Vec<#out_t>>, MatchError> {
+
+                #[allow(clippy::extra_unused_lifetimes, clippy::let_unit_value)]
+                fn matcher<#life>(input : &mut impl MatchInput<Item = #in_t>) -> Result<Success<#out_t>, MatchError> {
+                    #body
+                }
+
+                ::array_pattern::node(input, #kind, |input| ::array_pattern::repeat(input, #skip, matcher))
+            }
+        },
+        Mode::Maybe => quote! {
+            #[allow(clippy::extra_unused_lifetimes, clippy::let_unit_value)]
+            fn #name<#life>(input : &mut impl MatchInput<Item = #in_t>) -> Result<Success<Option<#out_t>>, MatchError> {
+
+                #[allow(clippy::extra_unused_lifetimes, clippy::let_unit_value)]
+                fn matcher<#life>(input : &mut impl MatchInput<Item = #in_t>) -> Result<Success<#out_t>, MatchError> {
+                    #body
+                }
+
+                ::array_pattern::node(input, #kind, |input| ::array_pattern::optional(matcher(input)))
+            }
+        },
+    }
+}
+
+pub fn expand_alt(alt : Alt) -> TokenStream {
+    let Header { name, life, in_t, out_t, .. } = &alt.header;
+    let kind = name.to_string();
+    let skip = skip_path(&alt.header);
+    let alternatives = alt.matchers.iter().map(|m| quote_spanned! { m.span() =>
+        match #m(input) {
+            Ok(v) => { return Ok(v); },
+            Err(e @ MatchError::Error(_)) => { _error = Some(e); },
+            Err(e @ MatchError::ErrorEndOfFile) => { _error = Some(e); },
+            Err(MatchError::Incomplete(n)) => {
+                ::array_pattern::reset(input, rp);
+                return Err(MatchError::Incomplete(n));
+            },
+            e @ Err(_) => { return e; },
+        }
+    });
+
+    quote! {
+        #[allow(clippy::extra_unused_lifetimes, clippy::let_unit_value)]
+        fn #name<#life>(input : &mut impl MatchInput<Item = #in_t>) -> Result<Success<#out_t>, MatchError> {
+            ::array_pattern::node(input, #kind, |input| {
+                let rp = input.checkpoint();
+                let mut _error : Option<MatchError> = None;
+
+                match #skip(input) {
+                    Ok(_) | Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => { },
+                    Err(MatchError::Incomplete(n)) => {
+                        ::array_pattern::reset(input, rp);
+                        return Err(MatchError::Incomplete(n));
+                    },
+                    Err(e) => { return Err(e); },
+                }
+
+                #(#alternatives)*
+
+                ::array_pattern::reset(input, rp);
+                Err(_error.unwrap())
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error<T : Parse>(src : &str, at : &str) -> (String, usize, usize) {
+        let e = syn::parse_str::<T>(src).err().unwrap();
+        (e.to_string(), e.span().start().column, src.find(at).unwrap())
+    }
+
+    #[test]
+    fn seq_errors_should_point_at_the_offending_token() {
+        let (message, column, expected) = error::<Seq>("digit<'a> : char => char = d <= '0'..='9'", "'0'");
+        assert_eq!( message, "missing action block; expected `, { ... }` after the last item" );
+        assert_eq!( column, expected );
+
+        let (message, column, expected) = error::<Seq>("digit<'a> : char => char = d '0', { d }", "'0'");
+        assert_eq!( message, "expected `<=` after `d`" );
+        assert_eq!( column, expected );
+
+        let (message, column, expected) = error::<Seq>("digit<'a> : char => char = d <= '0', { d } x", "x");
+        assert_eq!( message, "unexpected tokens after the action block; the `{ ... }` block must be the last item" );
+        assert_eq!( column, expected );
+
+        let (message, column, expected) = error::<Seq>("skip(ws) digit<'a> : char => char = d <= '0', { d }", "digit");
+        assert_eq!( message, "expected `~` after `skip(...)`" );
+        assert_eq!( column, expected );
+    }
+
+    #[test]
+    fn alt_errors_should_point_at_the_offending_token() {
+        let (message, column, expected) = error::<Alt>("maybe ~ op<'a> : char => char = plus | minus", "maybe");
+        assert_eq!( message, "`maybe` is only supported by `seq!`" );
+        assert_eq!( column, expected );
+
+        let (message, column, expected) = error::<Alt>("op<'a> : char => char = plus, minus", ",");
+        assert_eq!( message, "expected `|` between alternatives" );
+        assert_eq!( column, expected );
+
+        let (message, column, expected) = error::<Alt>("op<'a> : char char = plus | minus", "char =");
+        assert_eq!( message, "expected `=>` between the input and output types" );
+        assert_eq!( column, expected );
+    }
+}
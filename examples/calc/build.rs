@@ -0,0 +1,8 @@
+use std::path::Path;
+
+fn main() {
+    let out = Path::new(&std::env::var("OUT_DIR").unwrap()).join("calc.rs");
+    if let Err(e) = array_pattern::grammar::compile("src/calc.peg", out) {
+        panic!("{}", e);
+    }
+}
@@ -0,0 +1,37 @@
+use array_pattern::*;
+
+include!(concat!(env!("OUT_DIR"), "/calc.rs"));
+
+pub fn eval(s : &str) -> Result<i64, MatchError> {
+    let mut i = s.char_indices();
+    Ok(expr(&mut i)?.item)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_grammar_should_evaluate_expressions() -> Result<(), MatchError> {
+        assert_eq!( eval("1 + 2 * 3")?, 7 );
+        assert_eq!( eval("(1 + 2) * 3")?, 9 );
+        assert_eq!( eval("-4 / 2 - 1")?, -3 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn generated_grammar_should_support_predicates() -> Result<(), MatchError> {
+        let v = "let x";
+        let mut i = v.char_indices();
+
+        assert_eq!( keyword(&mut i)?.item, "let" );
+
+        let v = "lets";
+        let mut i = v.char_indices();
+
+        assert!( keyword(&mut i).is_err() );
+
+        Ok(())
+    }
+}
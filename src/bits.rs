@@ -0,0 +1,279 @@
+
+use crate::{MatchError, MatchInput, SliceInput, Success};
+
+pub fn bit_position(index : usize) -> (usize, u8) {
+    (index / 8, (index % 8) as u8)
+}
+
+#[derive(Debug)]
+pub struct BitInput<'a> {
+    data : &'a [u8],
+    offset : usize,
+    bit : usize,
+}
+
+impl<'a> BitInput<'a> {
+    pub fn new(data : &'a [u8]) -> Self {
+        BitInput { data, offset: 0, bit: 0 }
+    }
+
+    pub fn position(&self) -> (usize, u8) {
+        bit_position(self.offset * 8 + self.bit)
+    }
+
+    pub fn is_aligned(&self) -> bool {
+        self.bit.is_multiple_of(8)
+    }
+
+    pub fn into_bytes(self) -> SliceInput<'a, u8> {
+        let byte = self.bit.div_ceil(8).min(self.data.len());
+        SliceInput::with_offset(&self.data[byte..], self.offset + byte)
+    }
+}
+
+impl<'a> Clone for BitInput<'a> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a> Copy for BitInput<'a> { }
+
+impl<'a> Iterator for BitInput<'a> {
+    type Item = (usize, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.data.get(self.bit / 8)?;
+        let value = byte & (0x80 >> (self.bit % 8)) != 0;
+        let i = self.offset * 8 + self.bit;
+        self.bit += 1;
+        Some((i, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.data.len() * 8 - self.bit;
+        (len, Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for BitInput<'a> { }
+
+impl<'a> From<SliceInput<'a, u8>> for BitInput<'a> {
+    fn from(input : SliceInput<'a, u8>) -> Self {
+        BitInput { data: input.remaining(), offset: input.position(), bit: 0 }
+    }
+}
+
+pub fn flag(input : &mut impl MatchInput<Item = bool>) -> Result<Success<bool>, MatchError> {
+    crate::instrument(input, "flag", |input| {
+        let rp = input.checkpoint();
+        match input.next_item() {
+            Some((i, b)) => Ok(Success { start: i, end: i, item: b }),
+            None => {
+                let e = input.end_of_input(false);
+                crate::reset(input, rp);
+                Err(e)
+            },
+        }
+    })
+}
+
+pub fn byte_align(input : &mut impl MatchInput<Item = bool>) -> Result<Success<()>, MatchError> {
+    crate::instrument(input, "byte_align", |input| {
+        let mut rp = input.checkpoint();
+        let (start, mut end) = match input.next_item() {
+            Some((i, _)) if i.is_multiple_of(8) => {
+                crate::reset(input, rp);
+                return Ok(Success { start: i, end: i, item: () });
+            },
+            Some((i, _)) => (i, i),
+            None => {
+                crate::reset(input, rp);
+                return Ok(Success { start: 0, end: 0, item: () });
+            },
+        };
+        while !(end + 1).is_multiple_of(8) {
+            rp = input.checkpoint();
+            match input.next_item() {
+                Some((i, _)) => { end = i; },
+                None => {
+                    crate::reset(input, rp);
+                    break;
+                },
+            }
+        }
+        Ok(Success { start, end, item: () })
+    })
+}
+
+#[macro_export]
+macro_rules! bits {
+    ($matcher_name:ident<$life:lifetime> : bool => $out_t:ty = $n:expr) => {
+        bits!($matcher_name<$life> : bool => $out_t = $n, _);
+    };
+
+    ($matcher_name:ident<$life:lifetime> : bool => $out_t:ty = $n:expr, $p:pat) => {
+        #[allow(clippy::extra_unused_lifetimes, unreachable_patterns, clippy::redundant_pattern)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = bool>) -> Result<Success<$out_t>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                const _ : () = assert!(($n) as usize <= 64, concat!("bits!: `", stringify!($matcher_name), "` is wider than 64 bits"));
+                const _ : () = assert!(($n) as usize <= std::mem::size_of::<$out_t>() * 8, concat!("bits!: `", stringify!($matcher_name), "` is wider than `", stringify!($out_t), "`"));
+                let rp = input.checkpoint();
+                let mut value : u64 = 0;
+                let mut start : usize = 0;
+                let mut end : usize = 0;
+                for b in 0..$n {
+                    match input.next_item() {
+                        Some((i, bit)) => {
+                            if b == 0 {
+                                start = i;
+                            }
+                            end = i;
+                            value = (value << 1) | (bit as u64);
+                        },
+                        None => {
+                            let e = input.end_of_input(b != 0);
+                            $crate::reset(input, rp);
+                            return Err(e);
+                        },
+                    }
+                }
+                match (value as $out_t) {
+                    item @ $p => Ok(Success { start, end, item }),
+                    _ => {
+                        $crate::reset(input, rp);
+                        Err(MatchError::Error(start))
+                    },
+                }
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn bit_input_should_yield_most_significant_bit_first() {
+        let v : Vec<u8> = vec![0b1010_0000];
+        let mut i = BitInput::new(&v);
+
+        assert_eq!( i.next(), Some((0, true)) );
+        assert_eq!( i.next(), Some((1, false)) );
+        assert_eq!( i.next(), Some((2, true)) );
+        assert_eq!( i.position(), (0, 3) );
+    }
+
+    #[test]
+    fn bits_should_match_ipv4_flags_and_fragment_offset() -> Result<(), MatchError> {
+        struct Fragment {
+            dont_fragment : bool,
+            more_fragments : bool,
+            offset : u16,
+        }
+
+        bits!(fragment_offset<'a> : bool => u16 = 13);
+        seq!(fragment<'a> : bool => Fragment = _reserved <= false, dont_fragment <= flag, more_fragments <= flag, offset <= fragment_offset, {
+            Fragment { dont_fragment, more_fragments, offset }
+        });
+
+        let v : Vec<u8> = vec![0b0100_0001, 0x02, 0xFF];
+        let mut i = BitInput::new(&v);
+
+        let o = fragment(&mut i)?;
+
+        assert!( o.item.dont_fragment );
+        assert!( !o.item.more_fragments );
+        assert_eq!( o.item.offset, 0x0102 );
+        assert_eq!( bit_position(o.start), (0, 0) );
+        assert_eq!( bit_position(o.end), (1, 7) );
+
+        let mut bytes = i.into_bytes();
+
+        assert_eq!( bytes.next_item(), Some((2, &0xFF)) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn bits_should_match_bit_pattern() -> Result<(), MatchError> {
+        bits!(forbidden_zero<'a> : bool => u8 = 1, 0);
+        bits!(ref_idc<'a> : bool => u8 = 2);
+        bits!(unit_type<'a> : bool => u8 = 5, 1..=23);
+        seq!(nal_header<'a> : bool => (u8, u8) = _z <= forbidden_zero, r <= ref_idc, t <= unit_type, { (r, t) });
+
+        let v : Vec<u8> = vec![0x67, 0xFC];
+        let mut i = BitInput::new(&v);
+
+        let o = nal_header(&mut i)?;
+
+        assert_eq!( o.item, (3, 7) );
+
+        let o = nal_header(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Error(8)) ) );
+        assert_eq!( i.position(), (1, 0) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn bits_should_report_position_of_failing_pattern() {
+        bits!(version<'a> : bool => u8 = 4, 4);
+        bits!(length<'a> : bool => u8 = 4, 5..=15);
+        seq!(header<'a> : bool => () = _v <= version, _l <= length, { () });
+
+        let v : Vec<u8> = vec![0x42];
+        let mut i = BitInput::new(&v);
+
+        let o = header(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(4)) ) );
+        assert_eq!( bit_position(4), (0, 4) );
+    }
+
+    #[test]
+    fn bits_should_accept_full_width_fields() -> Result<(), MatchError> {
+        bits!(word<'a> : bool => u64 = 64);
+        bits!(byte<'a> : bool => u8 = 8);
+
+        let v : Vec<u8> = vec![0x80, 0, 0, 0, 0, 0, 0, 0x01, 0xFE];
+        let mut i = BitInput::new(&v);
+
+        assert_eq!( word(&mut i)?.item, 0x8000_0000_0000_0001 );
+        assert_eq!( byte(&mut i)?.item, 0xFE );
+
+        Ok(())
+    }
+
+    #[test]
+    fn byte_align_should_skip_to_next_byte() -> Result<(), MatchError> {
+        bits!(three<'a> : bool => u8 = 3);
+        seq!(main<'a> : bool => u8 = t <= three, _pad <= byte_align, _next <= true, { t });
+
+        let v : Vec<u8> = vec![0b1011_1111, 0x80];
+        let mut i = BitInput::new(&v);
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item, 0b101 );
+        assert_eq!( bit_position(o.end), (1, 0) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn bit_input_should_continue_from_byte_input() {
+        let v : Vec<u8> = vec![0xAA, 0x80];
+        let mut bytes = SliceInput::new(&v);
+
+        bytes.next_item();
+
+        let mut i = BitInput::from(bytes);
+
+        assert_eq!( i.next(), Some((8, true)) );
+        assert_eq!( i.position(), (1, 1) );
+    }
+}
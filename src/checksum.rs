@@ -0,0 +1,283 @@
+
+use std::borrow::Borrow;
+
+use crate::{MatchError, MatchInput};
+
+pub trait Checksum : Default {
+    fn update(&mut self, byte : u8);
+    fn finish(&self) -> u64;
+}
+
+#[derive(Debug)]
+pub struct Crc32 {
+    crc : u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32 { crc: 0xFFFFFFFF }
+    }
+}
+
+impl Checksum for Crc32 {
+    fn update(&mut self, byte : u8) {
+        self.crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (self.crc & 1).wrapping_neg();
+            self.crc = (self.crc >> 1) ^ (0xEDB88320 & mask);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        (!self.crc) as u64
+    }
+}
+
+#[derive(Debug)]
+pub struct Adler32 {
+    a : u32,
+    b : u32,
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Adler32 { a: 1, b: 0 }
+    }
+}
+
+impl Checksum for Adler32 {
+    fn update(&mut self, byte : u8) {
+        self.a = (self.a + byte as u32) % 65521;
+        self.b = (self.b + self.a) % 65521;
+    }
+
+    fn finish(&self) -> u64 {
+        ((self.b << 16) | self.a) as u64
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Sum8 {
+    sum : u8,
+}
+
+impl Checksum for Sum8 {
+    fn update(&mut self, byte : u8) {
+        self.sum = self.sum.wrapping_add(byte);
+    }
+
+    fn finish(&self) -> u64 {
+        self.sum as u64
+    }
+}
+
+#[derive(Debug)]
+pub struct Recording<'b, I> {
+    input : &'b mut I,
+    bytes : Vec<u8>,
+    first : Option<usize>,
+    last : usize,
+}
+
+impl<'b, I> Recording<'b, I> where I : MatchInput, I::Item : Borrow<u8> {
+    pub fn new(input : &'b mut I) -> Self {
+        Recording { input, bytes: vec![], first: None, last: 0 }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn span(&self) -> (usize, usize) {
+        match self.first {
+            Some(first) => (first, self.last),
+            None => (0, 0),
+        }
+    }
+
+    pub fn checksum<C : Checksum>(&self) -> u64 {
+        let mut c = C::default();
+        for b in &self.bytes {
+            c.update(*b);
+        }
+        c.finish()
+    }
+}
+
+impl<'b, I> MatchInput for Recording<'b, I> where I : MatchInput, I::Item : Borrow<u8> {
+    type Item = I::Item;
+    type Checkpoint = (I::Checkpoint, usize, Option<usize>, usize);
+
+    fn next_item(&mut self) -> Option<(usize, I::Item)> {
+        let (i, item) = self.input.next_item()?;
+        self.bytes.push(*item.borrow());
+        self.first.get_or_insert(i);
+        self.last = i;
+        Some((i, item))
+    }
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        (self.input.checkpoint(), self.bytes.len(), self.first, self.last)
+    }
+
+    fn restore(&mut self, checkpoint : Self::Checkpoint) {
+        self.input.restore(checkpoint.0);
+        self.bytes.truncate(checkpoint.1);
+        self.first = checkpoint.2;
+        self.last = checkpoint.3;
+    }
+
+    fn seek(&mut self, position : usize) -> bool {
+        self.input.seek(position)
+    }
+
+    fn can_seek(&self) -> bool {
+        self.input.can_seek()
+    }
+
+    fn is_partial(&self) -> bool {
+        self.input.is_partial()
+    }
+
+    fn start_node(&mut self, kind : &'static str) {
+        self.input.start_node(kind)
+    }
+
+    fn finish_node(&mut self, matched : bool) {
+        self.input.finish_node(matched)
+    }
+
+    fn end_of_input(&mut self, fatal : bool) -> MatchError {
+        self.input.end_of_input(fatal)
+    }
+}
+
+#[macro_export]
+macro_rules! checksum {
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $body:ident, $check:ident, $algorithm:ty) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                let rp = input.checkpoint();
+                let (body, actual, (start, end)) = {
+                    let mut recording = $crate::Recording::new(input);
+                    let body = $body(&mut recording)?;
+                    (body, recording.checksum::<$algorithm>(), recording.span())
+                };
+                let check = match $check(input) {
+                    Ok(check) => check,
+                    Err(MatchError::Error(i)) => return Err(MatchError::Fatal(i)),
+                    Err(MatchError::ErrorEndOfFile) => return Err(MatchError::FatalEndOfFile),
+                    Err(MatchError::Incomplete(n)) => {
+                        $crate::reset(input, rp);
+                        return Err(MatchError::Incomplete(n));
+                    },
+                    Err(e) => return Err(e),
+                };
+                let expected = check.item as u64;
+                if expected != actual {
+                    $crate::reset(input, rp);
+                    return Err(MatchError::Checksum { expected, actual, start, end });
+                }
+                Ok(Success { start: body.start, end: check.end, item: body.item })
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use crate::number::*;
+
+    fn compute<C : Checksum>(data : &[u8]) -> u64 {
+        let mut c = C::default();
+        for b in data {
+            c.update(*b);
+        }
+        c.finish()
+    }
+
+    #[test]
+    fn algorithms_should_match_reference_values() {
+        assert_eq!( compute::<Crc32>(b"123456789"), 0xCBF43926 );
+        assert_eq!( compute::<Adler32>(b"Wikipedia"), 0x11E60398 );
+        assert_eq!( compute::<Sum8>(&[0xFF, 0x02, 0x03]), 0x04 );
+    }
+
+    #[test]
+    fn checksum_should_accept_valid_crc() -> Result<(), MatchError> {
+        seq!(zero_or_more ~ payload<'a> : &'a u8 => u8 = b <= b'0'..=b'9', { *b });
+        checksum!(chunk<'a> : &'a u8 => Vec<u8> = payload, be_u32, Crc32);
+
+        let mut v : Vec<u8> = b"123456789".to_vec();
+        v.extend(0xCBF43926u32.to_be_bytes());
+        let mut i = SliceInput::new(&v);
+
+        let o = chunk(&mut i)?;
+
+        assert_eq!( o.item, b"123456789".to_vec() );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 12 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn checksum_should_report_expected_actual_and_span() {
+        seq!(header<'a> : u8 => () = _a <= 0xAA, { () });
+        seq!(payload<'a> : u8 => (u8, u8) = a <= _, b <= _, { (a, b) });
+        seq!(byte<'a> : u8 => u8 = b <= _, { b });
+        checksum!(chunk<'a> : u8 => (u8, u8) = payload, byte, Sum8);
+        seq!(main<'a> : u8 => (u8, u8) = _h <= header, c <= chunk, { c });
+
+        let v : Vec<u8> = vec![0xAA, 0x01, 0x02, 0x04];
+        let mut i = v.into_iter().enumerate();
+
+        let o = main(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Checksum { expected: 0x04, actual: 0x03, start: 1, end: 2 }) ) );
+    }
+
+    #[test]
+    fn checksum_should_allow_offsets_inside_body() -> Result<(), MatchError> {
+        seq!(byte<'a> : &'a u8 => u8 = b <= _, { *b });
+        at_offset!(pointer<'a> : &'a u8 => u8 = byte, byte);
+        seq!(body<'a> : &'a u8 => (u8, u8) = p <= pointer, b <= byte, { (p, b) });
+        checksum!(chunk<'a> : &'a u8 => (u8, u8) = body, byte, Sum8);
+
+        let v : Vec<u8> = vec![0x03, 0x07, 0x0A, 0x09];
+        let mut i = SliceInput::new(&v);
+
+        let o = chunk(&mut i)?;
+
+        assert_eq!( o.item, (0x09, 0x07) );
+        assert_eq!( o.end, 2 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn checksum_should_ignore_backtracked_items() -> Result<(), MatchError> {
+        seq!(long<'a> : u8 => u8 = a <= 0x01, _b <= 0x09, { a });
+        seq!(short<'a> : u8 => u8 = a <= 0x01, { a });
+        group!(either<'a> : u8 => u8 = |input| {
+            match long(input) {
+                Err(MatchError::Fatal(_)) => short(input),
+                r => r,
+            }
+        });
+        checksum!(chunk<'a> : u8 => u8 = either, be_u32, Adler32);
+
+        let v : Vec<u8> = vec![0x01, 0x00, 0x02, 0x00, 0x02];
+        let mut i = v.into_iter().enumerate();
+
+        let o = chunk(&mut i)?;
+
+        assert_eq!( o.item, 0x01 );
+        assert_eq!( o.end, 4 );
+
+        Ok(())
+    }
+}
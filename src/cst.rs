@@ -0,0 +1,270 @@
+
+use crate::{MatchError, MatchInput, Success};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Element<T> {
+    Node(Node<T>),
+    Token(usize, T),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node<T> {
+    pub kind : &'static str,
+    pub start : usize,
+    pub end : usize,
+    pub children : Vec<Element<T>>,
+}
+
+impl<T> Node<T> {
+    pub fn tokens(&self) -> Vec<(usize, &T)> {
+        let mut ret = vec![];
+        collect_tokens(&self.children, &mut ret);
+        ret
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = &T> {
+        self.tokens().into_iter().map(|(_, item)| item)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = &Node<T>> {
+        self.children.iter().filter_map(|e| match e {
+            Element::Node(n) => Some(n),
+            Element::Token(..) => None,
+        })
+    }
+
+    pub fn find(&self, kind : &str) -> Option<&Node<T>> {
+        if self.kind == kind {
+            return Some(self);
+        }
+        self.nodes().find_map(|n| n.find(kind))
+    }
+
+    pub fn find_mut(&mut self, kind : &str) -> Option<&mut Node<T>> {
+        if self.kind == kind {
+            return Some(self);
+        }
+        self.children.iter_mut().find_map(|e| match e {
+            Element::Node(n) => n.find_mut(kind),
+            Element::Token(..) => None,
+        })
+    }
+}
+
+fn collect_tokens<'t, T>(elements : &'t [Element<T>], ret : &mut Vec<(usize, &'t T)>) {
+    for e in elements {
+        match e {
+            Element::Node(n) => collect_tokens(&n.children, ret),
+            Element::Token(i, item) => ret.push((*i, item)),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Event<T> {
+    Open(&'static str),
+    Close,
+    Item(usize, T),
+}
+
+#[derive(Debug)]
+pub struct SyntaxInput<'b, I : MatchInput> {
+    input : &'b mut I,
+    events : Vec<Event<I::Item>>,
+}
+
+impl<'b, I> SyntaxInput<'b, I> where I : MatchInput, I::Item : Clone {
+    pub fn new(input : &'b mut I) -> Self {
+        SyntaxInput { input, events: vec![] }
+    }
+
+    pub fn into_tree(self, kind : &'static str) -> Node<I::Item> {
+        let mut stack = vec![(kind, vec![])];
+        for event in self.events {
+            match event {
+                Event::Open(kind) => stack.push((kind, vec![])),
+                Event::Close => {
+                    let (kind, children) = stack.pop().unwrap();
+                    let node = make_node(kind, children);
+                    stack.last_mut().unwrap().1.push(Element::Node(node));
+                },
+                Event::Item(i, item) => stack.last_mut().unwrap().1.push(Element::Token(i, item)),
+            }
+        }
+        while stack.len() > 1 {
+            let (kind, children) = stack.pop().unwrap();
+            let node = make_node(kind, children);
+            stack.last_mut().unwrap().1.push(Element::Node(node));
+        }
+        let (kind, children) = stack.pop().unwrap();
+        make_node(kind, children)
+    }
+}
+
+fn make_node<T>(kind : &'static str, children : Vec<Element<T>>) -> Node<T> {
+    let mut node = Node { kind, start: 0, end: 0, children };
+    let tokens = node.tokens();
+    if let (Some(first), Some(last)) = (tokens.first(), tokens.last()) {
+        let (start, end) = (first.0, last.0);
+        node.start = start;
+        node.end = end;
+    }
+    node
+}
+
+impl<'b, I> MatchInput for SyntaxInput<'b, I> where I : MatchInput, I::Item : Clone {
+    type Item = I::Item;
+    type Checkpoint = (I::Checkpoint, usize);
+
+    fn next_item(&mut self) -> Option<(usize, I::Item)> {
+        let (i, item) = self.input.next_item()?;
+        self.events.push(Event::Item(i, item.clone()));
+        Some((i, item))
+    }
+
+    fn checkpoint(&self) -> Self::Checkpoint {
+        (self.input.checkpoint(), self.events.len())
+    }
+
+    fn restore(&mut self, checkpoint : Self::Checkpoint) {
+        self.input.restore(checkpoint.0);
+        self.events.truncate(checkpoint.1);
+    }
+
+    fn seek(&mut self, position : usize) -> bool {
+        self.input.seek(position)
+    }
+
+    fn can_seek(&self) -> bool {
+        self.input.can_seek()
+    }
+
+    fn is_partial(&self) -> bool {
+        self.input.is_partial()
+    }
+
+    fn end_of_input(&mut self, fatal : bool) -> MatchError {
+        self.input.end_of_input(fatal)
+    }
+
+    fn start_node(&mut self, kind : &'static str) {
+        self.events.push(Event::Open(kind));
+    }
+
+    fn finish_node(&mut self, matched : bool) {
+        if matches!(self.events.last(), Some(Event::Open(_))) {
+            self.events.pop();
+            return;
+        }
+        if matched {
+            self.events.push(Event::Close);
+            return;
+        }
+        let mut depth = 0;
+        while let Some(event) = self.events.pop() {
+            match event {
+                Event::Close => depth += 1,
+                Event::Open(_) if depth == 0 => break,
+                Event::Open(_) => depth -= 1,
+                Event::Item(..) => { },
+            }
+        }
+    }
+}
+
+pub fn node<I, T>(input : &mut I, kind : &'static str, matcher : impl FnOnce(&mut I) -> Result<Success<T>, MatchError>) -> Result<Success<T>, MatchError>
+    where I : MatchInput {
+
+    crate::instrument(input, kind, |input| {
+        input.start_node(kind);
+        let result = matcher(input);
+        input.finish_node(result.is_ok());
+        result
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    pred!(space<'a> : char => char = |c : char| c.is_whitespace());
+    seq!(zero_or_more ~ ws<'a> : char => char = c <= space, { c });
+    seq!(zero_or_more ~ digits<'a> : char => char = d <= '0'..='9', { d });
+    seq!(number<'a> : char => u32 = d <= '0'..='9', rest <= digits, {
+        std::iter::once(d).chain(rest).collect::<String>().parse().unwrap()
+    });
+    seq!(skip(ws) ~ sum<'a> : char => u32 = a <= number, _p <= '+', b <= number, { a + b });
+
+    fn text(node : &Node<char>) -> String {
+        node.items().collect()
+    }
+
+    #[test]
+    fn syntax_input_should_build_lossless_tree() -> Result<(), MatchError> {
+        let v = "12 +  3";
+        let mut i = v.char_indices();
+        let mut s = SyntaxInput::new(&mut i);
+
+        let o = sum(&mut s)?;
+        let tree = s.into_tree("root");
+
+        assert_eq!( o.item, 15 );
+        assert_eq!( text(&tree), v );
+
+        let sum = tree.find("sum").unwrap();
+        assert_eq!( (sum.start, sum.end), (0, 6) );
+
+        let kinds : Vec<&str> = sum.nodes().map(|n| n.kind).collect();
+        assert_eq!( kinds, vec!["number", "ws", "ws", "number"] );
+
+        let second = sum.nodes().last().unwrap();
+        assert_eq!( (second.start, second.end), (6, 6) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn syntax_input_should_drop_backtracked_nodes() -> Result<(), MatchError> {
+        seq!(pair<'a> : char => char = a <= 'a', _b <= 'b', { a });
+        seq!(single<'a> : char => char = a <= 'a', { a });
+        group!(either<'a> : char => char = |input| {
+            match pair(input) {
+                Err(MatchError::Fatal(_)) => single(input),
+                r => r,
+            }
+        });
+        seq!(main<'a> : char => char = e <= either, _c <= 'c', { e });
+
+        let v = "ac";
+        let mut i = v.char_indices();
+        let mut s = SyntaxInput::new(&mut i);
+
+        main(&mut s)?;
+        let tree = s.into_tree("root");
+
+        let kinds : Vec<&str> = tree.find("main").unwrap().nodes().map(|n| n.kind).collect();
+        assert_eq!( kinds, vec!["single"] );
+        assert!( tree.find("pair").is_none() );
+        assert_eq!( text(&tree), "ac" );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tree_should_allow_structural_edits() -> Result<(), MatchError> {
+        let v = "1+2";
+        let mut i = v.char_indices();
+        let mut s = SyntaxInput::new(&mut i);
+
+        sum(&mut s)?;
+        let mut tree = s.into_tree("root");
+
+        let n = tree.find_mut("number").unwrap();
+        n.children = vec![Element::Token(0, '4'), Element::Token(0, '2')];
+
+        assert_eq!( text(&tree), "42+2" );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,432 @@
+
+use std::collections::BTreeSet;
+
+use crate::grammar::{split_top, Alternative, Element, Expr, Grammar, Rule};
+
+#[macro_export]
+macro_rules! grammar {
+    ($vis:vis fn $describe:ident; $($mac:ident ! ( $($body:tt)* );)*) => {
+        $( $mac!($($body)*); )*
+
+        $vis fn $describe() -> $crate::grammar::Grammar {
+            $crate::grammar::Grammar::describe(&[$((stringify!($mac), stringify!($($body)*))),*])
+        }
+    };
+}
+
+fn is_ident(s : &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_') && chars.all(|c| c.is_alphanumeric() || c == '_') && !matches!(s, "_" | "true" | "false")
+}
+
+fn closing(s : &str, open : char, close : char) -> Option<usize> {
+    let mut depth = 0;
+    for (k, c) in s.char_indices() {
+        if c == open {
+            depth += 1;
+        }
+        else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(k);
+            }
+        }
+    }
+    None
+}
+
+fn assignment(s : &str) -> Option<usize> {
+    let mut depth = 0i32;
+    let mut chars = s.char_indices().peekable();
+    while let Some((k, c)) = chars.next() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            '-' if matches!(chars.peek(), Some((_, '>'))) => { chars.next(); },
+            '=' if matches!(chars.peek(), Some((_, '>'))) => { chars.next(); },
+            '=' if depth == 0 => return Some(k),
+            _ => { },
+        }
+    }
+    None
+}
+
+fn placeholder(k : usize, mac : &str, body : &str) -> Rule {
+    let name = body.split('<').next().and_then(|h| h.rsplit(['~', ' ']).find(|w| !w.is_empty())).filter(|n| is_ident(n));
+    let name = name.map(str::to_string).unwrap_or_else(|| format!("{}!{}", mac, k));
+    let element = Element { label: None, expr: Expr::Opaque(format!("{}!", mac)), line: 0 };
+    Rule { name, ty: String::new(), alternatives: vec![Alternative { elements: vec![element], action: None, line: 0 }], line: 0 }
+}
+
+fn describe_rule(mac : &str, body : &str) -> Option<Rule> {
+    let mut body = body.trim();
+    let mut mode = "";
+    for m in ["zero_or_more", "maybe"] {
+        if let Some(rest) = body.strip_prefix(m).and_then(|r| r.trim_start().strip_prefix('~')) {
+            mode = m;
+            body = rest.trim_start();
+        }
+    }
+    if body.starts_with("skip") {
+        body = body[closing(body, '(', ')')? + 1..].trim_start().strip_prefix('~')?.trim_start();
+    }
+
+    let name = body[..body.find('<')?].trim().to_string();
+    let header = body.find("=>")?;
+    let eq = header + 2 + assignment(&body[header + 2..])?;
+    let out = body[header + 2..eq].trim();
+    let rest = body[eq + 1..].trim();
+
+    let element = |label : Option<&str>, item : &str| {
+        let item = item.trim();
+        let expr = if is_ident(item) { Expr::Rule(item.to_string()) } else { Expr::Pattern(item.to_string()) };
+        Element { label: label.map(str::to_string), expr, line: 0 }
+    };
+
+    let (ty, alternatives) = match mac {
+        "alt" => {
+            let alternatives = split_top(rest, "|").into_iter().map(|m| Alternative { elements: vec![element(None, m)], action: None, line: 0 }).collect();
+            (out.to_string(), alternatives)
+        },
+        "seq" => {
+            let mut elements = vec![];
+            for item in split_top(rest, ",") {
+                let item = item.trim();
+                if item.starts_with('{') || item.is_empty() {
+                    continue;
+                }
+                let parts = split_top(item, "<=");
+                let (label, item) = match parts.as_slice() {
+                    [label, item] => (Some(label.trim()), *item),
+                    _ => (None, item),
+                };
+                elements.push(element(label, item));
+            }
+            let alternative = Alternative { elements, action: None, line: 0 };
+            match mode {
+                "zero_or_more" => (format!("Vec<{}>", out), vec![Alternative { elements: vec![Element { label: None, expr: Expr::Many(Box::new(Expr::Group(vec![alternative]))), line: 0 }], action: None, line: 0 }]),
+                "maybe" => (format!("Option<{}>", out), vec![Alternative { elements: vec![Element { label: None, expr: Expr::Optional(Box::new(Expr::Group(vec![alternative]))), line: 0 }], action: None, line: 0 }]),
+                _ => (out.to_string(), vec![alternative]),
+            }
+        },
+        mac => {
+            let expr = match mac {
+                "pred" => Expr::Pred(rest.to_string()),
+                "group" => Expr::Opaque("group".to_string()),
+                _ => Expr::Opaque(format!("{}!", mac)),
+            };
+            (out.to_string(), vec![Alternative { elements: vec![Element { label: None, expr, line: 0 }], action: None, line: 0 }])
+        },
+    };
+    Some(Rule { name, ty, alternatives, line: 0 })
+}
+
+pub(crate) fn text(expr : &Expr) -> String {
+    let inner = |e : &Expr| match e {
+        Expr::Group(_) | Expr::Rule(_) | Expr::Pattern(_) | Expr::Str(_) | Expr::Any => text(e),
+        _ => format!("({})", text(e)),
+    };
+    match expr {
+        Expr::Rule(name) => name.clone(),
+        Expr::Pattern(p) => p.clone(),
+        Expr::Str(s) => format!("{:?}", s),
+        Expr::Any => ".".to_string(),
+        Expr::Pred(_) => "predicate".to_string(),
+        Expr::Opaque(label) => label.clone(),
+        Expr::Group(alternatives) => {
+            let alternatives : Vec<String> = alternatives.iter().map(|a| a.elements.iter().map(|e| text(&e.expr)).collect::<Vec<_>>().join(" ")).collect();
+            format!("({})", alternatives.join(" / "))
+        },
+        Expr::Many(e) => format!("{}*", inner(e)),
+        Expr::Many1(e) => format!("{}+", inner(e)),
+        Expr::Optional(e) => format!("{}?", inner(e)),
+        Expr::And(e) => format!("&{}", inner(e)),
+        Expr::Not(e) => format!("!{}", inner(e)),
+    }
+}
+
+fn escape(s : &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+enum Diagram {
+    Skip,
+    Box { text : String, terminal : bool },
+    Seq(Vec<Diagram>),
+    Choice(Vec<Diagram>),
+    Loop(Box<Diagram>),
+}
+
+const CHAR : usize = 8;
+const HALF : usize = 11;
+const GAP : usize = 10;
+const RAIL : usize = 20;
+
+impl Diagram {
+    fn new(alternatives : &[Alternative]) -> Diagram {
+        let mut choices : Vec<Diagram> = alternatives.iter().map(|a| Diagram::Seq(a.elements.iter().map(|e| Diagram::expr(&e.expr)).collect())).collect();
+        if choices.len() == 1 {
+            choices.pop().unwrap()
+        }
+        else {
+            Diagram::Choice(choices)
+        }
+    }
+
+    fn expr(expr : &Expr) -> Diagram {
+        match expr {
+            Expr::Rule(name) | Expr::Opaque(name) => Diagram::Box { text: name.clone(), terminal: false },
+            Expr::Group(alternatives) => Diagram::new(alternatives),
+            Expr::Many(e) => Diagram::Choice(vec![Diagram::Skip, Diagram::Loop(Box::new(Diagram::expr(e)))]),
+            Expr::Many1(e) => Diagram::Loop(Box::new(Diagram::expr(e))),
+            Expr::Optional(e) => Diagram::Choice(vec![Diagram::Skip, Diagram::expr(e)]),
+            e => Diagram::Box { text: text(e), terminal: true },
+        }
+    }
+
+    fn width(&self) -> usize {
+        match self {
+            Diagram::Skip => 0,
+            Diagram::Box { text, .. } => text.chars().count() * CHAR + 2 * GAP,
+            Diagram::Seq(items) => items.iter().map(|d| d.width()).sum::<usize>() + GAP * items.len().saturating_sub(1),
+            Diagram::Choice(items) => items.iter().map(|d| d.width()).max().unwrap_or(0) + 2 * RAIL,
+            Diagram::Loop(item) => item.width() + 2 * RAIL,
+        }
+    }
+
+    fn up(&self) -> usize {
+        match self {
+            Diagram::Skip => 0,
+            Diagram::Box { .. } => HALF,
+            Diagram::Seq(items) => items.iter().map(|d| d.up()).max().unwrap_or(0),
+            Diagram::Choice(items) => items[0].up(),
+            Diagram::Loop(item) => item.up(),
+        }
+    }
+
+    fn down(&self) -> usize {
+        match self {
+            Diagram::Skip => 0,
+            Diagram::Box { .. } => HALF,
+            Diagram::Seq(items) => items.iter().map(|d| d.down()).max().unwrap_or(0),
+            Diagram::Choice(items) => items[0].down() + items[1..].iter().map(|d| GAP + d.up() + d.down()).sum::<usize>(),
+            Diagram::Loop(item) => item.down() + GAP,
+        }
+    }
+
+    fn draw(&self, x : usize, y : usize, out : &mut String) {
+        let line = |out : &mut String, x1 : usize, y1 : usize, x2 : usize, y2 : usize| {
+            *out += &format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", x1, y1, x2, y2);
+        };
+        match self {
+            Diagram::Skip => { },
+            Diagram::Box { text, terminal } => {
+                let w = self.width();
+                let rx = if *terminal { HALF } else { 0 };
+                *out += &format!("<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"{}\" class=\"{}\"/>\n", x, y - HALF, w, 2 * HALF, rx, if *terminal { "terminal" } else { "rule" });
+                *out += &format!("<text x=\"{}\" y=\"{}\">{}</text>\n", x + w / 2, y + 4, escape(text));
+            },
+            Diagram::Seq(items) => {
+                let mut cx = x;
+                for (k, item) in items.iter().enumerate() {
+                    if k != 0 {
+                        line(out, cx, y, cx + GAP, y);
+                        cx += GAP;
+                    }
+                    item.draw(cx, y, out);
+                    cx += item.width();
+                }
+            },
+            Diagram::Choice(items) => {
+                let w = self.width();
+                let mut cy = y;
+                for (k, item) in items.iter().enumerate() {
+                    if k != 0 {
+                        cy += items[k - 1].down() + GAP + item.up();
+                    }
+                    line(out, x + RAIL / 2, cy, x + RAIL, cy);
+                    item.draw(x + RAIL, cy, out);
+                    line(out, x + RAIL + item.width(), cy, x + w - RAIL / 2, cy);
+                }
+                line(out, x, y, x + RAIL / 2, y);
+                line(out, x + RAIL / 2, y, x + RAIL / 2, cy);
+                line(out, x + w - RAIL / 2, y, x + w - RAIL / 2, cy);
+                line(out, x + w - RAIL / 2, y, x + w, y);
+            },
+            Diagram::Loop(item) => {
+                let w = self.width();
+                let back = y + item.down() + GAP;
+                line(out, x, y, x + RAIL, y);
+                item.draw(x + RAIL, y, out);
+                line(out, x + RAIL + item.width(), y, x + w, y);
+                line(out, x + w - RAIL / 2, y, x + w - RAIL / 2, back);
+                line(out, x + w - RAIL / 2, back, x + RAIL / 2, back);
+                line(out, x + RAIL / 2, back, x + RAIL / 2, y);
+            },
+        }
+    }
+}
+
+impl Grammar {
+    pub fn describe(definitions : &[(&str, &str)]) -> Grammar {
+        let rules = definitions.iter().enumerate().map(|(k, (mac, body))| describe_rule(mac, body).unwrap_or_else(|| placeholder(k, mac, body))).collect();
+        Grammar { input: String::new(), rules }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut ret = "digraph grammar {\n    node [shape=box];\n".to_string();
+        let mut external = BTreeSet::new();
+        for rule in &self.rules {
+            ret += &format!("    \"{}\";\n", rule.name);
+        }
+        for rule in &self.rules {
+            let mut refs = BTreeSet::new();
+            fn walk<'g>(alternatives : &'g [Alternative], refs : &mut BTreeSet<&'g str>) {
+                for e in alternatives.iter().flat_map(|a| &a.elements) {
+                    walk_expr(&e.expr, refs);
+                }
+            }
+            fn walk_expr<'g>(expr : &'g Expr, refs : &mut BTreeSet<&'g str>) {
+                match expr {
+                    Expr::Rule(name) => { refs.insert(name); },
+                    Expr::Group(alternatives) => walk(alternatives, refs),
+                    Expr::Many(e) | Expr::Many1(e) | Expr::Optional(e) | Expr::And(e) | Expr::Not(e) => walk_expr(e, refs),
+                    _ => { },
+                }
+            }
+            walk(&rule.alternatives, &mut refs);
+            for r in refs {
+                if self.rule(r).is_none() {
+                    external.insert(r);
+                }
+                ret += &format!("    \"{}\" -> \"{}\";\n", rule.name, r);
+            }
+        }
+        for r in external {
+            ret += &format!("    \"{}\" [style=dashed];\n", r);
+        }
+        ret += "}\n";
+        ret
+    }
+
+    pub fn rule_svg(&self, name : &str) -> Option<String> {
+        let rule = self.rule(name)?;
+        let diagram = Diagram::new(&rule.alternatives);
+        let (w, up, down) = (diagram.width(), diagram.up(), diagram.down());
+        let title = 2 * HALF;
+        let width = w + 2 * RAIL + 2 * GAP;
+        let height = title + up + down + 2 * GAP;
+        let y = title + GAP + up;
+        let mut out = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" class=\"railroad\">\n", width, height);
+        out += "<style>line, rect { stroke: black; stroke-width: 1.5; fill: none; } text { font: 13px monospace; text-anchor: middle; } .title { text-anchor: start; font-weight: bold; }</style>\n";
+        out += &format!("<text x=\"{}\" y=\"{}\" class=\"title\">{}</text>\n", GAP, HALF + 4, escape(name));
+        out += &format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", GAP, y - HALF / 2, GAP, y + HALF / 2);
+        out += &format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", GAP, y, GAP + RAIL, y);
+        diagram.draw(GAP + RAIL, y, &mut out);
+        out += &format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", GAP + RAIL + w, y, width - GAP, y);
+        out += &format!("<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"/>\n", width - GAP, y - HALF / 2, width - GAP, y + HALF / 2);
+        out += "</svg>\n";
+        Some(out)
+    }
+
+    pub fn to_svg(&self) -> String {
+        let mut ret = "<html>\n<body>\n".to_string();
+        for rule in &self.rules {
+            ret += &self.rule_svg(&rule.name).unwrap();
+        }
+        ret += "</body>\n</html>\n";
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::grammar::{Expr, Grammar};
+
+    grammar! {
+        fn calc;
+        pred!(space<'a> : char => char = |c : char| c.is_whitespace());
+        seq!(zero_or_more ~ ws<'a> : char => char = c <= space, { c });
+        seq!(zero_or_more ~ digits<'a> : char => char = d <= '0'..='9', { d });
+        seq!(number<'a> : char => u32 = d <= '0'..='9', rest <= digits, {
+            std::iter::once(d).chain(rest).collect::<String>().parse().unwrap()
+        });
+        seq!(plus<'a> : char => () = _p <= '+' | '-', { () });
+        seq!(maybe ~ sign<'a> : char => char = s <= '-', { s });
+        seq!(skip(ws) ~ sum<'a> : char => u32 = _s <= sign, a <= number, _o <= plus, b <= number, { a + b });
+        alt!(value<'a> : char => u32 = sum | number);
+    }
+
+    #[test]
+    fn grammar_should_define_matchers_and_describe_them() -> Result<(), MatchError> {
+        assert_eq!( value(&mut "1 + 22".char_indices())?.item, 23 );
+
+        let g = calc();
+
+        assert_eq!( g.rules.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["space", "ws", "digits", "number", "plus", "sign", "sum", "value"] );
+        assert_eq!( g.rule("ws").unwrap().ty, "Vec<char>" );
+        assert_eq!( g.rule("number").unwrap().alternatives[0].elements[1].expr, Expr::Rule("digits".into()) );
+        assert_eq!( g.rule("plus").unwrap().alternatives[0].elements[0].expr, Expr::Pattern("'+' | '-'".into()) );
+        assert_eq!( g.rule("sum").unwrap().alternatives[0].elements.len(), 4 );
+        assert_eq!( g.rule("value").unwrap().alternatives.len(), 2 );
+        assert!( matches!( g.rule("sign").unwrap().alternatives[0].elements[0].expr, Expr::Optional(_) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn describe_should_keep_every_definition() {
+        let g = Grammar::describe(&[
+            ("seq", "skip(ws::<(char, char)>) ~ pair<'a> : char => (char, char) = a <= 'a', b <= 'b', { (a, b) }"),
+            ("seq", "boxed<'a> : char => Box<dyn Iterator<Item = char>> = a <= 'a', { Box::new(std::iter::once(a)) }"),
+            ("group", "g<'a> : char => char = |input| { letter(input) }"),
+            ("pred", "letter<'a> : char => char = |c : char| c.is_alphabetic()"),
+            ("bits", "flag<'a> : bool => u8 = 1"),
+            ("seq", "broken"),
+        ]);
+
+        assert_eq!( g.rules.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["pair", "boxed", "g", "letter", "flag", "broken"] );
+        assert_eq!( g.rule("pair").unwrap().alternatives[0].elements.len(), 2 );
+        assert_eq!( g.rule("boxed").unwrap().ty, "Box<dyn Iterator<Item = char>>" );
+        assert_eq!( g.rule("boxed").unwrap().alternatives[0].elements[0].expr, Expr::Pattern("'a'".into()) );
+        assert_eq!( g.rule("g").unwrap().alternatives[0].elements[0].expr, Expr::Opaque("group".into()) );
+        assert!( matches!( g.rule("letter").unwrap().alternatives[0].elements[0].expr, Expr::Pred(_) ) );
+        assert_eq!( g.rule("flag").unwrap().alternatives[0].elements[0].expr, Expr::Opaque("bits!".into()) );
+        assert_eq!( g.rule("broken").unwrap().alternatives[0].elements[0].expr, Expr::Opaque("seq!".into()) );
+        assert!( g.rule_svg("g").unwrap().contains(">group</text>") );
+    }
+
+    #[test]
+    fn to_dot_should_list_rule_dependencies() {
+        let dot = calc().to_dot();
+
+        assert!( dot.starts_with("digraph grammar {\n") );
+        assert!( dot.contains("    \"value\" -> \"number\";\n    \"value\" -> \"sum\";\n") );
+        assert!( dot.contains("    \"ws\" -> \"space\";\n") );
+
+        let g = Grammar::parse("a = b 'x' ;\nb = 'y' c ;\nc = 'z' ;\n").unwrap();
+        let g = Grammar { rules: g.rules[..2].to_vec(), ..g };
+
+        assert!( g.to_dot().contains("    \"c\" [style=dashed];\n") );
+    }
+
+    #[test]
+    fn svg_should_draw_terminals_and_rules() {
+        let g = calc();
+        let svg = g.rule_svg("sum").unwrap();
+
+        assert!( svg.starts_with("<svg ") );
+        assert!( svg.ends_with("</svg>\n") );
+        assert!( svg.contains(">number</text>") );
+        assert!( g.rule_svg("plus").unwrap().contains(">'+' | '-'</text>") );
+        assert!( g.rule_svg("nope").is_none() );
+
+        let g = Grammar::parse("list = item (',' item)* ';'? ;\nitem = ['a'..='z']+ / \"<nil>\" ;\n").unwrap();
+        let html = g.to_svg();
+
+        assert_eq!( html.matches("<svg ").count(), 2 );
+        assert!( html.contains(">&quot;&lt;nil&gt;&quot;</text>") );
+        assert!( html.contains("class=\"terminal\"") );
+    }
+}
@@ -0,0 +1,22 @@
+
+use std::borrow::Cow;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceEvent {
+    Enter { depth : usize, matcher : Cow<'static, str>, position : Option<usize> },
+    Exit { depth : usize, matcher : Cow<'static, str>, result : Result<(usize, usize), String> },
+    Backtrack { depth : usize, position : Option<usize> },
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = |position : &Option<usize>| position.map(|p| format!("at {}", p)).unwrap_or_else(|| "at end of input".to_string());
+        match self {
+            TraceEvent::Enter { depth, matcher, position } => write!(f, "{:indent$}{} {}", "", matcher, at(position), indent = depth * 2),
+            TraceEvent::Exit { depth, matcher, result: Ok((start, end)) } => write!(f, "{:indent$}{} matched {}..={}", "", matcher, start, end, indent = depth * 2),
+            TraceEvent::Exit { depth, matcher, result: Err(e) } => write!(f, "{:indent$}{} failed: {}", "", matcher, e, indent = depth * 2),
+            TraceEvent::Backtrack { depth, position } => write!(f, "{:indent$}backtrack {}", "", at(position), indent = depth * 2),
+        }
+    }
+}
@@ -0,0 +1,371 @@
+
+use crate::{MatchError, MatchInput, Success};
+
+#[derive(Debug)]
+pub struct Bounded<'b, I> {
+    input : &'b mut I,
+    start : Option<usize>,
+    limit : usize,
+    consumed : usize,
+    overrun : Option<usize>,
+}
+
+impl<'b, I : MatchInput> Bounded<'b, I> {
+    pub fn new(input : &'b mut I, limit : usize) -> Self {
+        let start = crate::peek_position(input);
+        Bounded { input, start, limit, consumed: 0, overrun: None }
+    }
+
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.limit - self.consumed
+    }
+}
+
+impl<'b, I : MatchInput> MatchInput for Bounded<'b, I> {
+    type Item = I::Item;
+    type Checkpoint = (I::Checkpoint, usize);
+
+    fn next_item(&mut self) -> Option<(usize, I::Item)> {
+        if self.consumed >= self.limit {
+            if self.overrun.is_none() {
+                self.overrun = Some(crate::peek_position(self.input).unwrap_or(self.start.map_or(0, |s| s + self.limit)));
+            }
+            return None;
+        }
+        let item = self.input.next_item()?;
+        self.consumed += 1;
+        Some(item)
+    }
+
+    fn checkpoint(&self) -> (I::Checkpoint, usize) {
+        (self.input.checkpoint(), self.consumed)
+    }
+
+    fn restore(&mut self, checkpoint : (I::Checkpoint, usize)) {
+        self.input.restore(checkpoint.0);
+        self.consumed = checkpoint.1;
+    }
+
+    fn seek(&mut self, position : usize) -> bool {
+        match self.start {
+            Some(start) if position >= start && position - start <= self.limit && self.input.seek(position) => {
+                self.consumed = position - start;
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn can_seek(&self) -> bool {
+        self.input.can_seek()
+    }
+
+    fn is_partial(&self) -> bool {
+        self.consumed < self.limit && self.input.is_partial()
+    }
+
+    fn start_node(&mut self, kind : &'static str) {
+        self.input.start_node(kind)
+    }
+
+    fn finish_node(&mut self, matched : bool) {
+        self.input.finish_node(matched)
+    }
+
+    fn end_of_input(&mut self, fatal : bool) -> MatchError {
+        if self.consumed < self.limit {
+            if self.input.is_partial() {
+                return MatchError::Incomplete(Some(self.limit - self.consumed));
+            }
+            return self.input.end_of_input(fatal);
+        }
+        if fatal {
+            MatchError::FatalEndOfFile
+        }
+        else {
+            MatchError::ErrorEndOfFile
+        }
+    }
+}
+
+pub fn exactly<I, T>(input : &mut I, n : usize, matcher : impl FnOnce(&mut Bounded<'_, I>) -> Result<Success<T>, MatchError>) -> Result<Success<T>, MatchError>
+    where I : MatchInput {
+
+    let rp = input.checkpoint();
+    let mut bounded = Bounded::new(input, n);
+    let result = matcher(&mut bounded);
+    let remaining = bounded.remaining();
+    let overrun = bounded.overrun;
+
+    match (result, overrun) {
+        (Ok(s), _) if remaining == 0 => Ok(s),
+        (Err(MatchError::ErrorEndOfFile), Some(position)) | (Err(MatchError::FatalEndOfFile), Some(position)) => {
+            crate::reset(input, rp);
+            Err(MatchError::Fatal(position))
+        },
+        (Ok(_), _) => {
+            let e = match input.next_item() {
+                Some((i, _)) => MatchError::Fatal(i),
+                None => input.end_of_input(true),
+            };
+            crate::reset(input, rp);
+            Err(e)
+        },
+        (Err(e), _) => Err(e),
+    }
+}
+
+#[macro_export]
+macro_rules! length_prefixed {
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $length:ident, $body:ident) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                let rp = input.checkpoint();
+                let len = $length(input)?;
+                match $crate::exactly(input, len.item as usize, |i| $body(i)) {
+                    Ok(s) => Ok(Success { start: len.start, end: std::cmp::max(len.end, s.end), item: s.item }),
+                    Err(MatchError::Error(i)) => Err(MatchError::Fatal(i)),
+                    Err(MatchError::ErrorEndOfFile) => Err(MatchError::FatalEndOfFile),
+                    Err(MatchError::Incomplete(n)) => {
+                        $crate::reset(input, rp);
+                        Err(MatchError::Incomplete(n))
+                    },
+                    Err(e) => Err(e),
+                }
+            })
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! tlv {
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $tag:ident, $length:ident, $($p:pat => $m:ident),+ $(,)?) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                let rp = input.checkpoint();
+                let tag = $tag(input)?;
+                let len = match $length(input) {
+                    Ok(len) => len,
+                    Err(MatchError::Error(i)) => return Err(MatchError::Fatal(i)),
+                    Err(MatchError::ErrorEndOfFile) => return Err(MatchError::FatalEndOfFile),
+                    Err(MatchError::Incomplete(n)) => {
+                        $crate::reset(input, rp);
+                        return Err(MatchError::Incomplete(n));
+                    },
+                    Err(e) => return Err(e),
+                };
+                let result = match tag.item {
+                    $( $p => $crate::exactly(input, len.item as usize, |i| $m(i)), )+
+                    #[allow(unreachable_patterns)]
+                    _ => {
+                        $crate::reset(input, rp);
+                        return Err(MatchError::Error(tag.start));
+                    },
+                };
+                match result {
+                    Ok(s) => Ok(Success { start: tag.start, end: std::cmp::max(len.end, s.end), item: s.item }),
+                    Err(MatchError::Error(i)) => Err(MatchError::Fatal(i)),
+                    Err(MatchError::ErrorEndOfFile) => Err(MatchError::FatalEndOfFile),
+                    Err(MatchError::Incomplete(n)) => {
+                        $crate::reset(input, rp);
+                        Err(MatchError::Incomplete(n))
+                    },
+                    Err(e) => Err(e),
+                }
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use crate::number::*;
+
+    seq!(byte<'a> : u8 => u8 = b <= _, { b });
+    seq!(zero_or_more ~ text<'a> : u8 => char = c <= b'a'..=b'z', { c as char });
+
+    #[test]
+    fn exactly_should_bound_matcher_to_n_items() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![b'a', b'b', b'c', b'd'];
+        let mut i = v.into_iter().enumerate();
+
+        let o = exactly(&mut i, 3, |i| text(i))?;
+
+        assert_eq!( o.item, vec!['a', 'b', 'c'] );
+        assert_eq!( i.next(), Some((3, b'd')) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn exactly_should_fail_on_under_consumption() {
+        let v : Vec<u8> = vec![b'a', b'b', b'1', b'd'];
+        let mut i = v.into_iter().enumerate();
+
+        let o = exactly(&mut i, 4, |i| text(i));
+
+        assert!( matches!( o, Err(MatchError::Fatal(2)) ) );
+        assert_eq!( i.next(), Some((0, b'a')) );
+    }
+
+    #[test]
+    fn exactly_should_fail_on_over_consumption() {
+        seq!(pair<'a> : u8 => (u8, u8) = a <= _, b <= _, { (a, b) });
+
+        let v : Vec<u8> = vec![b'a', b'b'];
+        let mut i = v.into_iter().enumerate();
+
+        let o = exactly(&mut i, 1, |i| pair(i));
+
+        assert!( matches!( o, Err(MatchError::Fatal(1)) ) );
+        assert_eq!( i.next(), Some((0, b'a')) );
+
+        let v : Vec<u8> = vec![b'a'];
+        let mut i = v.into_iter().enumerate();
+
+        let o = exactly(&mut i, 1, |i| pair(i));
+
+        assert!( matches!( o, Err(MatchError::Fatal(1)) ) );
+    }
+
+    #[test]
+    fn exactly_should_fail_on_truncated_input() {
+        seq!(pair<'a> : u8 => (u8, u8) = a <= _, b <= _, { (a, b) });
+
+        let v : Vec<u8> = vec![b'a'];
+        let mut i = v.into_iter().enumerate();
+
+        let o = exactly(&mut i, 2, |i| pair(i));
+
+        assert!( matches!( o, Err(MatchError::FatalEndOfFile) ) );
+    }
+
+    #[test]
+    fn length_prefixed_should_parse_frame() -> Result<(), MatchError> {
+        length_prefixed!(name<'a> : u8 => Vec<char> = byte, text);
+        seq!(main<'a> : u8 => (Vec<char>, u8) = n <= name, b <= byte, { (n, b) });
+
+        let v : Vec<u8> = vec![0x02, b'h', b'i', 0xFF];
+        let mut i = v.into_iter().enumerate();
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item, (vec!['h', 'i'], 0xFF) );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 3 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn length_prefixed_should_report_fatal_for_short_body() {
+        length_prefixed!(name<'a> : u8 => Vec<char> = byte, text);
+
+        let v : Vec<u8> = vec![0x03, b'h', b'i'];
+        let mut i = v.into_iter().enumerate();
+
+        let o = name(&mut i);
+
+        assert!( matches!( o, Err(MatchError::FatalEndOfFile) ) );
+    }
+
+    #[test]
+    fn length_prefixed_should_indicate_incomplete_with_missing_count() {
+        length_prefixed!(name<'a> : &'a u8 => Vec<u8> = be_u16, body);
+        seq!(zero_or_more ~ body<'a> : &'a u8 => u8 = b <= _, { *b });
+
+        let v : Vec<u8> = vec![0x00, 0x05, 0x01, 0x02];
+        let mut i = Partial::new(SliceInput::new(&v));
+
+        let o = name(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(Some(3))) ) );
+        assert_eq!( i.get_ref().position(), 0 );
+    }
+
+    #[test]
+    fn length_prefixed_should_match_complete_frame_at_end_of_partial_input() -> Result<(), MatchError> {
+        length_prefixed!(name<'a> : &'a u8 => Vec<u8> = byte, letters);
+        seq!(byte<'a> : &'a u8 => u8 = b <= _, { *b });
+        seq!(zero_or_more ~ letters<'a> : &'a u8 => u8 = l <= b'a'..=b'z', { *l });
+
+        let v : Vec<u8> = vec![0x02, b'a', b'b'];
+        let mut i = Partial::new(SliceInput::new(&v));
+
+        let o = name(&mut i)?;
+
+        assert_eq!( o.item, vec![b'a', b'b'] );
+        assert_eq!( o.end, 2 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn length_prefixed_should_seek_only_within_frame() -> Result<(), MatchError> {
+        seq!(byte<'a> : &'a u8 => u8 = b <= _, { *b });
+        at_offset!(pointer<'a> : &'a u8 => u8 = byte, byte);
+        seq!(record<'a> : &'a u8 => (u8, u8) = p <= pointer, b <= byte, { (p, b) });
+        length_prefixed!(frame<'a> : &'a u8 => (u8, u8) = byte, record);
+
+        let v : Vec<u8> = vec![0x02, 0x02, 0x0B, 0x0C];
+        let mut i = SliceInput::new(&v);
+
+        let o = frame(&mut i)?;
+
+        assert_eq!( o.item, (0x0B, 0x0B) );
+        assert_eq!( i.position(), 3 );
+
+        let v : Vec<u8> = vec![0x02, 0x03, 0x0B, 0x0C];
+        let mut i = SliceInput::new(&v);
+
+        assert!( matches!( frame(&mut i), Err(MatchError::Fatal(3)) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tlv_should_dispatch_on_tag() -> Result<(), MatchError> {
+        #[derive(Debug, PartialEq)]
+        enum Field {
+            Name(Vec<char>),
+            Port(u16),
+        }
+
+        seq!(name<'a> : u8 => Field = t <= text, { Field::Name(t) });
+        seq!(port<'a> : u8 => Field = p <= be_u16, { Field::Port(p) });
+        tlv!(field<'a> : u8 => Field = byte, byte, 0x01 => name, 0x02 => port);
+        seq!(zero_or_more ~ fields<'a> : u8 => Field = f <= field, { f });
+
+        let v : Vec<u8> = vec![0x02, 0x02, 0x1F, 0x90, 0x01, 0x03, b'w', b'e', b'b', 0x09, 0x00];
+        let mut i = v.into_iter().enumerate();
+
+        let o = fields(&mut i)?;
+
+        assert_eq!( o.item, vec![Field::Port(8080), Field::Name(vec!['w', 'e', 'b'])] );
+        assert_eq!( o.end, 8 );
+        assert_eq!( i.next(), Some((9, 0x09)) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn tlv_should_report_value_that_does_not_fill_frame() {
+        seq!(port<'a> : u8 => u16 = p <= be_u16, { p });
+        tlv!(field<'a> : u8 => u16 = byte, byte, 0x02 => port);
+
+        let v : Vec<u8> = vec![0x02, 0x03, 0x1F, 0x90, 0x00];
+        let mut i = v.into_iter().enumerate();
+
+        let o = field(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(4)) ) );
+    }
+}
@@ -0,0 +1,1459 @@
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::{MatchError, MatchInput, Success, TraceEvent};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GrammarError {
+    pub line : usize,
+    pub column : usize,
+    pub message : String,
+}
+
+impl GrammarError {
+    fn new(line : usize, message : impl Into<String>) -> Self {
+        GrammarError { line, column: 0, message: message.into() }
+    }
+
+    fn at(line : usize, column : usize, message : impl Into<String>) -> Self {
+        GrammarError { line, column, message: message.into() }
+    }
+}
+
+impl fmt::Display for GrammarError {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.message);
+        }
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for GrammarError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemType {
+    Char,
+    Byte,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grammar {
+    pub input : String,
+    pub rules : Vec<Rule>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub name : String,
+    pub ty : String,
+    pub alternatives : Vec<Alternative>,
+    pub line : usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alternative {
+    pub elements : Vec<Element>,
+    pub action : Option<String>,
+    pub line : usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    pub label : Option<String>,
+    pub expr : Expr,
+    pub line : usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Rule(String),
+    Pattern(String),
+    Str(String),
+    Any,
+    Pred(String),
+    Opaque(String),
+    Group(Vec<Alternative>),
+    Many(Box<Expr>),
+    Many1(Box<Expr>),
+    Optional(Box<Expr>),
+    And(Box<Expr>),
+    Not(Box<Expr>),
+}
+
+struct Parser<'s> {
+    src : &'s [u8],
+    pos : usize,
+    line : usize,
+}
+
+impl<'s> Parser<'s> {
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, n : usize) -> Option<u8> {
+        self.src.get(self.pos + n).copied()
+    }
+
+    fn bump(&mut self) -> Option<u8> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == b'\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn column(&self, pos : usize) -> usize {
+        let start = self.src[..pos].iter().rposition(|&c| c == b'\n').map_or(0, |i| i + 1);
+        String::from_utf8_lossy(&self.src[start..pos]).chars().count() + 1
+    }
+
+    fn error<T>(&self, message : impl Into<String>) -> Result<T, GrammarError> {
+        Err(GrammarError::at(self.line, self.column(self.pos), message))
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == b'#' {
+                while !matches!(self.peek(), None | Some(b'\n')) {
+                    self.bump();
+                }
+            }
+            else if c.is_ascii_whitespace() {
+                self.bump();
+            }
+            else {
+                break;
+            }
+        }
+    }
+
+    fn eat(&mut self, c : u8) -> bool {
+        self.skip_space();
+        if self.peek() == Some(c) {
+            self.bump();
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c : u8, what : &str) -> Result<(), GrammarError> {
+        if self.eat(c) {
+            Ok(())
+        }
+        else {
+            self.error(format!("expected {}", what))
+        }
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        self.skip_space();
+        let start = self.pos;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == b'_' => { },
+            _ => return None,
+        }
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == b'_') {
+            self.bump();
+        }
+        Some(String::from_utf8_lossy(&self.src[start..self.pos]).into_owned())
+    }
+
+    fn text(&self, start : usize) -> String {
+        String::from_utf8_lossy(&self.src[start..self.pos]).trim().to_string()
+    }
+
+    fn skip_quoted(&mut self) -> Result<(), GrammarError> {
+        let (line, column) = (self.line, self.column(self.pos));
+        match self.peek() {
+            Some(b'"') => {
+                self.bump();
+                loop {
+                    match self.bump() {
+                        Some(b'\\') => { self.bump(); },
+                        Some(b'"') => return Ok(()),
+                        Some(_) => { },
+                        None => return Err(GrammarError::at(line, column, "unterminated string literal")),
+                    }
+                }
+            },
+            Some(b'\'') => {
+                self.bump();
+                if self.peek() == Some(b'\\') {
+                    while !matches!(self.peek(), None | Some(b'\'')) {
+                        self.bump();
+                    }
+                    self.bump();
+                }
+                else {
+                    let width = match self.peek() {
+                        Some(0xF0..) => 4,
+                        Some(0xE0..) => 3,
+                        Some(0xC0..) => 2,
+                        _ => 1,
+                    };
+                    if self.peek_at(width) == Some(b'\'') {
+                        for _ in 0..=width {
+                            self.bump();
+                        }
+                    }
+                }
+                Ok(())
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn balanced(&mut self, open : u8, close : u8, what : &str) -> Result<String, GrammarError> {
+        let (line, column) = (self.line, self.column(self.pos));
+        let start = self.pos;
+        let mut depth = 1;
+        loop {
+            match self.peek() {
+                Some(b'"') | Some(b'\'') => self.skip_quoted()?,
+                Some(c) if c == open => {
+                    depth += 1;
+                    self.bump();
+                },
+                Some(c) if c == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let text = self.text(start);
+                        self.bump();
+                        return Ok(text);
+                    }
+                    self.bump();
+                },
+                Some(_) => { self.bump(); },
+                None => return Err(GrammarError::at(line, column, format!("unterminated {}", what))),
+            }
+        }
+    }
+
+    fn type_text(&mut self) -> Result<String, GrammarError> {
+        self.skip_space();
+        let start = self.pos;
+        let mut depth = 0;
+        loop {
+            match self.peek() {
+                Some(b'<') | Some(b'(') | Some(b'[') => depth += 1,
+                Some(b'>') | Some(b')') | Some(b']') => depth -= 1,
+                Some(b'=') if depth == 0 && self.peek_at(1) != Some(b'>') => break,
+                Some(b'\n') | None => return self.error("expected `=` after the rule type"),
+                _ => { },
+            }
+            self.bump();
+        }
+        let ty = self.text(start);
+        if ty.is_empty() {
+            return self.error("expected a type after `:`");
+        }
+        Ok(ty)
+    }
+
+    fn grammar(&mut self) -> Result<Grammar, GrammarError> {
+        let mut input = "char".to_string();
+        let mut rules : Vec<Rule> = vec![];
+        loop {
+            self.skip_space();
+            if self.peek().is_none() {
+                break;
+            }
+            if self.eat(b'@') {
+                let directive = self.ident().unwrap_or_default();
+                if directive != "input" {
+                    return self.error(format!("unknown directive `@{}`", directive));
+                }
+                while matches!(self.peek(), Some(b' ') | Some(b'\t')) {
+                    self.bump();
+                }
+                let start = self.pos;
+                while !matches!(self.peek(), None | Some(b'\n') | Some(b'#')) {
+                    self.bump();
+                }
+                input = self.text(start);
+                if input.is_empty() {
+                    return self.error("expected a type after `@input`");
+                }
+                continue;
+            }
+            let (line, column) = (self.line, self.column(self.pos));
+            let name = match self.ident() {
+                Some(name) => name,
+                None => return self.error("expected a rule name"),
+            };
+            if rules.iter().any(|r| r.name == name) {
+                return Err(GrammarError::at(line, column, format!("rule `{}` is defined more than once", name)));
+            }
+            let ty = if self.eat(b':') { self.type_text()? } else { "()".to_string() };
+            self.expect(b'=', "`=` after the rule name")?;
+            let alternatives = self.alternatives()?;
+            self.expect(b';', "`;` at the end of the rule")?;
+            rules.push(Rule { name, ty, alternatives, line });
+        }
+        Ok(Grammar { input, rules })
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Alternative>, GrammarError> {
+        let mut ret = vec![self.sequence()?];
+        while self.eat(b'/') {
+            ret.push(self.sequence()?);
+        }
+        Ok(ret)
+    }
+
+    fn sequence(&mut self) -> Result<Alternative, GrammarError> {
+        self.skip_space();
+        let line = self.line;
+        let mut elements = vec![];
+        let mut action = None;
+        loop {
+            self.skip_space();
+            match self.peek() {
+                Some(b'{') if self.peek_at(1) != Some(b'?') => {
+                    self.bump();
+                    action = Some(self.balanced(b'{', b'}', "action block")?);
+                    break;
+                },
+                Some(b';') | Some(b'/') | Some(b')') | None => break,
+                _ => elements.push(self.element()?),
+            }
+        }
+        if elements.is_empty() {
+            return self.error("expected at least one element");
+        }
+        Ok(Alternative { elements, action, line })
+    }
+
+    fn element(&mut self) -> Result<Element, GrammarError> {
+        self.skip_space();
+        let line = self.line;
+        let save = (self.pos, self.line);
+        let mut label = None;
+        if let Some(name) = self.ident() {
+            if self.peek() == Some(b':') && self.peek_at(1) != Some(b':') {
+                self.bump();
+                label = Some(name);
+            }
+            else {
+                (self.pos, self.line) = save;
+            }
+        }
+        let expr = self.prefix()?;
+        Ok(Element { label, expr, line })
+    }
+
+    fn prefix(&mut self) -> Result<Expr, GrammarError> {
+        if self.eat(b'&') {
+            return Ok(Expr::And(Box::new(self.suffix()?)));
+        }
+        if self.eat(b'!') {
+            return Ok(Expr::Not(Box::new(self.suffix()?)));
+        }
+        self.suffix()
+    }
+
+    fn suffix(&mut self) -> Result<Expr, GrammarError> {
+        let mut expr = self.primary()?;
+        loop {
+            if self.eat(b'*') {
+                expr = Expr::Many(Box::new(expr));
+            }
+            else if self.eat(b'+') {
+                expr = Expr::Many1(Box::new(expr));
+            }
+            else if self.eat(b'?') {
+                expr = Expr::Optional(Box::new(expr));
+            }
+            else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, GrammarError> {
+        self.skip_space();
+        let start = self.pos;
+        match self.peek() {
+            Some(b'(') => {
+                self.bump();
+                let alternatives = self.alternatives()?;
+                self.expect(b')', "`)` to close the group")?;
+                Ok(Expr::Group(alternatives))
+            },
+            Some(b'[') => {
+                self.bump();
+                let pattern = self.balanced(b'[', b']', "pattern")?;
+                if pattern.is_empty() {
+                    return self.error("empty pattern `[]`");
+                }
+                Ok(Expr::Pattern(pattern))
+            },
+            Some(b'{') => {
+                self.bump();
+                self.bump();
+                Ok(Expr::Pred(self.balanced(b'{', b'}', "predicate")?))
+            },
+            Some(b'.') => {
+                self.bump();
+                Ok(Expr::Any)
+            },
+            Some(b'"') => {
+                self.skip_quoted()?;
+                let text = self.text(start);
+                Ok(Expr::Str(unescape(&text[1..text.len() - 1])))
+            },
+            Some(b'\'') => {
+                self.skip_quoted()?;
+                Ok(Expr::Pattern(self.text(start)))
+            },
+            Some(b'b') if self.peek_at(1) == Some(b'\'') => {
+                self.bump();
+                self.skip_quoted()?;
+                Ok(Expr::Pattern(self.text(start)))
+            },
+            Some(c) if c.is_ascii_digit() => {
+                while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == b'_') {
+                    self.bump();
+                }
+                Ok(Expr::Pattern(self.text(start)))
+            },
+            _ => match self.ident() {
+                Some(name) => Ok(Expr::Rule(name)),
+                None => match self.peek() {
+                    Some(c) => self.error(format!("unexpected `{}`", c as char)),
+                    None => self.error("unexpected end of grammar"),
+                },
+            },
+        }
+    }
+}
+
+fn unescape(s : &str) -> String {
+    let mut ret = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            ret.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => ret.push('\n'),
+            Some('r') => ret.push('\r'),
+            Some('t') => ret.push('\t'),
+            Some('0') => ret.push('\0'),
+            Some(c) => ret.push(c),
+            None => { },
+        }
+    }
+    ret
+}
+
+impl Grammar {
+    pub fn parse(src : &str) -> Result<Grammar, GrammarError> {
+        let mut parser = Parser { src: src.as_bytes(), pos: 0, line: 1 };
+        let grammar = parser.grammar()?;
+        grammar.check()?;
+        Ok(grammar)
+    }
+
+    pub fn define(&mut self, src : &str) -> Result<Vec<String>, GrammarError> {
+        let mut parser = Parser { src: src.as_bytes(), pos: 0, line: 1 };
+        let defined = parser.grammar()?;
+        let mut names = vec![];
+        for rule in defined.rules {
+            names.push(rule.name.clone());
+            match self.rules.iter_mut().find(|r| r.name == rule.name) {
+                Some(r) => *r = rule,
+                None => self.rules.push(rule),
+            }
+        }
+        Ok(names)
+    }
+
+    pub fn rule(&self, name : &str) -> Option<&Rule> {
+        self.rules.iter().find(|r| r.name == name)
+    }
+
+    pub fn item_type(&self) -> Result<ItemType, GrammarError> {
+        let ty = self.input.trim().trim_start_matches('&').trim_start();
+        let ty = match ty.strip_prefix('\'') {
+            Some(lifetime) => lifetime.split_once(char::is_whitespace).map(|(_, ty)| ty.trim()).unwrap_or(""),
+            None => ty,
+        };
+        match ty {
+            "char" => Ok(ItemType::Char),
+            "u8" => Ok(ItemType::Byte),
+            _ => Err(GrammarError::new(0, format!("unsupported item type `{}`; expected `char` or `u8`", self.input))),
+        }
+    }
+
+    pub fn check(&self) -> Result<(), GrammarError> {
+        fn walk(grammar : &Grammar, alternatives : &[Alternative]) -> Result<(), GrammarError> {
+            for alternative in alternatives {
+                for element in &alternative.elements {
+                    walk_expr(grammar, &element.expr, element.line)?;
+                }
+            }
+            Ok(())
+        }
+        fn walk_expr(grammar : &Grammar, expr : &Expr, line : usize) -> Result<(), GrammarError> {
+            match expr {
+                Expr::Rule(name) if grammar.rule(name).is_none() => Err(GrammarError::new(line, format!("undefined rule `{}`", name))),
+                Expr::Group(alternatives) => walk(grammar, alternatives),
+                Expr::Many(e) | Expr::Many1(e) | Expr::Optional(e) | Expr::And(e) | Expr::Not(e) => walk_expr(grammar, e, line),
+                _ => Ok(()),
+            }
+        }
+        for rule in &self.rules {
+            walk(self, &rule.alternatives)?;
+        }
+        Ok(())
+    }
+
+    pub fn to_rust(&self) -> Result<String, GrammarError> {
+        let types = self.rules.iter().map(|r| (r.name.as_str(), r.ty.clone())).collect();
+        let mut generator = Generator { grammar: self, types, out: String::new(), counter: 0, rule: String::new() };
+        for rule in &self.rules {
+            generator.rule = rule.name.clone();
+            generator.counter = 0;
+            generator.rule_body(&rule.name, &rule.ty, &rule.alternatives, false)?;
+        }
+        Ok(generator.out)
+    }
+}
+
+struct Generator<'g> {
+    grammar : &'g Grammar,
+    types : HashMap<&'g str, String>,
+    out : String,
+    counter : usize,
+    rule : String,
+}
+
+impl<'g> Generator<'g> {
+    fn fresh(&mut self) -> String {
+        let name = format!("__{}_{}", self.rule, self.counter);
+        self.counter += 1;
+        name
+    }
+
+    fn input(&self) -> &str {
+        &self.grammar.input
+    }
+
+    fn bytes(&self, line : usize) -> Result<bool, GrammarError> {
+        match self.grammar.item_type() {
+            Ok(ty) => Ok(ty == ItemType::Byte),
+            Err(e) => Err(GrammarError::new(line, format!("string literals need `char` or `u8` items: {}", e.message))),
+        }
+    }
+
+    fn rule_body(&mut self, name : &str, ty : &str, alternatives : &[Alternative], group : bool) -> Result<(), GrammarError> {
+        if alternatives.len() == 1 {
+            return self.sequence(name, ty, &alternatives[0], group);
+        }
+        let mut names = vec![];
+        for alternative in alternatives {
+            let aux = self.fresh();
+            self.sequence(&aux, ty, alternative, group)?;
+            names.push(aux);
+        }
+        self.out += &format!("alt!({}<'a> : {} => {} = {});\n", name, self.input(), ty, names.join(" | "));
+        Ok(())
+    }
+
+    fn sequence(&mut self, name : &str, ty : &str, alternative : &Alternative, group : bool) -> Result<(), GrammarError> {
+        let mut items = vec![];
+        let mut bindings = vec![];
+        for (k, element) in alternative.elements.iter().enumerate() {
+            let binding = element.label.clone().unwrap_or_else(|| format!("_e{}", k));
+            let item = self.item(&element.expr, element.line)?;
+            items.push(format!("{} <= {}", binding, item));
+            bindings.push(binding);
+        }
+        let action = match &alternative.action {
+            Some(action) => action.clone(),
+            None if ty == "()" => "()".to_string(),
+            None if group && bindings.len() == 1 => bindings[0].clone(),
+            None if group => format!("({})", bindings.join(", ")),
+            None => match alternative.elements.as_slice() {
+                [single] if same_type(&self.type_of(&single.expr)?, ty) => bindings[0].clone(),
+                [single] => return Err(GrammarError::new(alternative.line, format!("rule `{}` of type `{}` needs an action; `{}` produces `{}`", self.rule, ty, crate::diagram::text(&single.expr), self.type_of(&single.expr)?))),
+                _ => return Err(GrammarError::new(alternative.line, format!("rule `{}` of type `{}` needs an action", self.rule, ty))),
+            },
+        };
+        self.out += &format!("seq!({}<'a> : {} => {} = {}, {{ {} }});\n", name, self.input(), ty, items.join(", "), action);
+        Ok(())
+    }
+
+    fn type_of(&self, expr : &Expr) -> Result<String, GrammarError> {
+        Ok(match expr {
+            Expr::Rule(name) => self.types[name.as_str()].clone(),
+            Expr::Pattern(_) | Expr::Any | Expr::Pred(_) => self.input().to_string(),
+            Expr::Opaque(label) => return Err(GrammarError::new(0, format!("`{}` cannot be compiled", label))),
+            Expr::Str(_) => "&'static str".to_string(),
+            Expr::Many(e) | Expr::Many1(e) => format!("Vec<{}>", self.type_of(e)?),
+            Expr::Optional(e) => format!("Option<{}>", self.type_of(e)?),
+            Expr::And(_) | Expr::Not(_) => "()".to_string(),
+            Expr::Group(alternatives) => {
+                let mut ty : Option<String> = None;
+                for alternative in alternatives {
+                    if alternative.action.is_some() {
+                        return Err(GrammarError::new(alternative.line, "actions are not supported inside groups; move the group into its own rule"));
+                    }
+                    let t = match alternative.elements.as_slice() {
+                        [single] => self.type_of(&single.expr)?,
+                        elements => {
+                            let types = elements.iter().map(|e| self.type_of(&e.expr)).collect::<Result<Vec<_>, _>>()?;
+                            format!("({})", types.join(", "))
+                        },
+                    };
+                    match &ty {
+                        Some(prev) if *prev != t => return Err(GrammarError::new(alternative.line, format!("alternatives of a group have different types `{}` and `{}`", prev, t))),
+                        _ => ty = Some(t),
+                    }
+                }
+                ty.unwrap_or_else(|| "()".to_string())
+            },
+        })
+    }
+
+    fn item(&mut self, expr : &Expr, line : usize) -> Result<String, GrammarError> {
+        match expr {
+            Expr::Pattern(p) if p.contains('|') => Ok(format!("({})", p)),
+            Expr::Pattern(p) => Ok(p.clone()),
+            Expr::Any => Ok("_".to_string()),
+            Expr::Rule(name) => Ok(name.clone()),
+            _ => self.matcher(expr, line),
+        }
+    }
+
+    fn matcher(&mut self, expr : &Expr, line : usize) -> Result<String, GrammarError> {
+        match expr {
+            Expr::Rule(name) => return Ok(name.clone()),
+            Expr::Opaque(label) => return Err(GrammarError::new(line, format!("`{}` cannot be compiled", label))),
+            _ => { },
+        }
+        let ty = self.type_of(expr)?;
+        let input = self.input().to_string();
+        let name = self.fresh();
+        match expr {
+            Expr::Pattern(_) | Expr::Any => {
+                let item = self.item(expr, line)?;
+                self.out += &format!("seq!({}<'a> : {} => {} = x <= {}, {{ x }});\n", name, input, ty, item);
+            },
+            Expr::Pred(closure) => {
+                self.out += &format!("pred!({}<'a> : {} => {} = {});\n", name, input, ty, closure);
+            },
+            Expr::Str(s) => {
+                if s.is_empty() {
+                    return Err(GrammarError::new(line, "empty string literal"));
+                }
+                let items : Vec<String> = if self.bytes(line)? {
+                    if !s.is_ascii() {
+                        return Err(GrammarError::new(line, "string literals must be ASCII for byte inputs"));
+                    }
+                    s.bytes().enumerate().map(|(k, b)| format!("_{} <= b'\\x{:02x}'", k, b)).collect()
+                }
+                else {
+                    s.chars().enumerate().map(|(k, c)| format!("_{} <= {:?}", k, c)).collect()
+                };
+                self.out += &format!("seq!({}<'a> : {} => {} = {}, {{ {:?} }});\n", name, input, ty, items.join(", "), s);
+            },
+            Expr::Many(e) => {
+                let item = self.item(e, line)?;
+                let elem = self.type_of(e)?;
+                self.out += &format!("seq!(zero_or_more ~ {}<'a> : {} => {} = x <= {}, {{ x }});\n", name, input, elem, item);
+            },
+            Expr::Many1(e) => {
+                let item = self.item(e, line)?;
+                let rest = self.matcher(&Expr::Many(e.clone()), line)?;
+                self.out += &format!("seq!({}<'a> : {} => {} = x <= {}, rest <= {}, {{ let mut v = vec![x]; v.extend(rest); v }});\n", name, input, ty, item, rest);
+            },
+            Expr::Optional(e) => {
+                let item = self.item(e, line)?;
+                let elem = self.type_of(e)?;
+                self.out += &format!("seq!(maybe ~ {}<'a> : {} => {} = x <= {}, {{ x }});\n", name, input, elem, item);
+            },
+            Expr::And(e) => {
+                let m = self.matcher(e, line)?;
+                self.out += &format!(concat!(
+                    "group!({}<'a> : {} => () = |input| {{\n",
+                    "    let at = peek_position(input).unwrap_or(0);\n",
+                    "    let rp = input.checkpoint();\n",
+                    "    let result = {}(input);\n",
+                    "    reset(input, rp);\n",
+                    "    match result {{\n",
+                    "        Ok(_) => Ok(Success {{ item: (), start: at, end: at }}),\n",
+                    "        Err(MatchError::Fatal(i)) => Err(MatchError::Error(i)),\n",
+                    "        Err(MatchError::FatalEndOfFile) => Err(MatchError::ErrorEndOfFile),\n",
+                    "        Err(e) => Err(e),\n",
+                    "    }}\n",
+                    "}});\n"), name, input, m);
+            },
+            Expr::Not(e) => {
+                let m = self.matcher(e, line)?;
+                self.out += &format!(concat!(
+                    "group!({}<'a> : {} => () = |input| {{\n",
+                    "    let at = peek_position(input).unwrap_or(0);\n",
+                    "    let rp = input.checkpoint();\n",
+                    "    let result = {}(input);\n",
+                    "    reset(input, rp);\n",
+                    "    match result {{\n",
+                    "        Ok(s) => Err(MatchError::Error(s.start)),\n",
+                    "        Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) | Err(MatchError::Fatal(_)) | Err(MatchError::FatalEndOfFile) => Ok(Success {{ item: (), start: at, end: at }}),\n",
+                    "        Err(e) => Err(e),\n",
+                    "    }}\n",
+                    "}});\n"), name, input, m);
+            },
+            Expr::Group(alternatives) => {
+                let alternatives = alternatives.clone();
+                self.rule_body(&name, &ty, &alternatives, true)?;
+            },
+            Expr::Rule(_) | Expr::Opaque(_) => unreachable!(),
+        }
+        Ok(name)
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tree {
+    pub rule : String,
+    pub start : usize,
+    pub end : usize,
+    pub children : Vec<Tree>,
+}
+
+impl fmt::Display for Tree {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write(tree : &Tree, depth : usize, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+            writeln!(f, "{:indent$}{} {}..={}", "", tree.rule, tree.start, tree.end, indent = depth * 2)?;
+            for child in &tree.children {
+                write(child, depth + 1, f)?;
+            }
+            Ok(())
+        }
+        write(self, 0, f)
+    }
+}
+
+fn same_type(a : &str, b : &str) -> bool {
+    a.split_whitespace().eq(b.split_whitespace())
+}
+
+pub(crate) fn split_top<'p>(text : &'p str, sep : &str) -> Vec<&'p str> {
+    let mut ret = vec![];
+    let mut depth = 0;
+    let mut last = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((k, c)) = chars.next() {
+        match c {
+            '\'' => {
+                let rest = &text[k + 1..];
+                let mut literal = rest.chars();
+                let len = match (literal.next(), literal.next()) {
+                    (Some('\\'), _) => rest.get(2..).and_then(|r| r.find('\'')).map(|n| n + 3),
+                    (Some(c), Some('\'')) => Some(c.len_utf8() + 1),
+                    _ => None,
+                };
+                if let Some(len) = len {
+                    while matches!(chars.peek(), Some(&(i, _)) if i <= k + len) {
+                        chars.next();
+                    }
+                }
+            },
+            '"' => {
+                while let Some((_, c)) = chars.next() {
+                    match c {
+                        '\\' => { chars.next(); },
+                        '"' => break,
+                        _ => { },
+                    }
+                }
+            },
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            _ if depth == 0 && text[k..].starts_with(sep) => {
+                ret.push(&text[last..k]);
+                last = k + sep.len();
+                while matches!(chars.peek(), Some(&(i, _)) if i < last) {
+                    chars.next();
+                }
+            },
+            _ => { },
+        }
+    }
+    ret.push(&text[last..]);
+    ret
+}
+
+fn literal(text : &str) -> Option<u32> {
+    let text = text.trim();
+    if let Some(rest) = text.strip_prefix('b') {
+        return literal(rest).filter(|&b| b < 256);
+    }
+    if let Some(inner) = text.strip_prefix('\'').and_then(|t| t.strip_suffix('\'')) {
+        let mut chars = inner.chars();
+        let c = match (chars.next()?, chars.next()) {
+            (c, None) => c,
+            ('\\', Some(e)) => match e {
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                '0' => '\0',
+                'x' => return u32::from_str_radix(&inner[2..], 16).ok(),
+                'u' => return u32::from_str_radix(inner[2..].trim_start_matches('{').trim_end_matches('}'), 16).ok(),
+                e => e,
+            },
+            _ => return None,
+        };
+        return Some(c as u32);
+    }
+    let digits : String = text.chars().filter(|&c| c != '_').collect();
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, &digits[..]),
+    };
+    let digits = match digits.find(['u', 'i']) {
+        Some(k) => &digits[..k],
+        None => digits,
+    };
+    u32::from_str_radix(digits, radix).ok()
+}
+
+pub(crate) fn class(pattern : &str) -> Option<Vec<(u32, u32)>> {
+    let mut pattern = pattern.trim();
+    while pattern.starts_with('(') && pattern.ends_with(')') && split_top(pattern, "|").len() == 1 {
+        pattern = pattern[1..pattern.len() - 1].trim();
+    }
+    let mut ret = vec![];
+    for alternative in split_top(pattern, "|") {
+        let alternative = alternative.trim();
+        if alternative == "_" {
+            ret.push((0, u32::MAX));
+        }
+        else if let [a, b] = split_top(alternative, "..=")[..] {
+            ret.push((literal(a)?, literal(b)?));
+        }
+        else if let [a, b] = split_top(alternative, "..")[..] {
+            ret.push((literal(a)?, literal(b)?.checked_sub(1)?));
+        }
+        else {
+            let v = literal(alternative)?;
+            ret.push((v, v));
+        }
+    }
+    Some(ret)
+}
+
+pub type TreeResult = Result<Success<Tree>, MatchError>;
+
+pub struct Interpreter {
+    grammar : Grammar,
+    item_type : ItemType,
+    classes : HashMap<String, Vec<(u32, u32)>>,
+    tracing : Cell<bool>,
+    depth : Cell<usize>,
+    steps : RefCell<Vec<TraceEvent>>,
+}
+
+impl Interpreter {
+    pub fn new(grammar : Grammar) -> Result<Interpreter, GrammarError> {
+        fn walk(alternatives : &[Alternative], bytes : bool, classes : &mut HashMap<String, Vec<(u32, u32)>>) -> Result<(), GrammarError> {
+            for alternative in alternatives {
+                for element in &alternative.elements {
+                    walk_expr(&element.expr, element.line, bytes, classes)?;
+                }
+            }
+            Ok(())
+        }
+        fn walk_expr(expr : &Expr, line : usize, bytes : bool, classes : &mut HashMap<String, Vec<(u32, u32)>>) -> Result<(), GrammarError> {
+            match expr {
+                Expr::Pattern(p) => match class(p) {
+                    Some(c) => { classes.insert(p.clone(), c); },
+                    None => return Err(GrammarError::new(line, format!("pattern `{}` cannot be interpreted; only literals, ranges, `_` and `|` are supported", p))),
+                },
+                Expr::Pred(_) => return Err(GrammarError::new(line, "predicates cannot be interpreted")),
+                Expr::Opaque(label) => return Err(GrammarError::new(line, format!("`{}` cannot be interpreted", label))),
+                Expr::Str(s) if bytes && !s.is_ascii() => return Err(GrammarError::new(line, "string literals must be ASCII for byte inputs")),
+                Expr::Group(alternatives) => walk(alternatives, bytes, classes)?,
+                Expr::Many(e) | Expr::Many1(e) | Expr::Optional(e) | Expr::And(e) | Expr::Not(e) => walk_expr(e, line, bytes, classes)?,
+                _ => { },
+            }
+            Ok(())
+        }
+        grammar.check()?;
+        let item_type = grammar.item_type()?;
+        let mut classes = HashMap::new();
+        let bytes = item_type == ItemType::Byte;
+        for rule in &grammar.rules {
+            walk(&rule.alternatives, bytes, &mut classes)?;
+        }
+        Ok(Interpreter { grammar, item_type, classes, tracing: Cell::new(false), depth: Cell::new(0), steps: RefCell::new(vec![]) })
+    }
+
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    pub fn item_type(&self) -> ItemType {
+        self.item_type
+    }
+
+    pub fn run<I>(&self, start : &str, input : &mut I) -> Result<TreeResult, GrammarError>
+        where I : MatchInput, I::Item : Into<u32> {
+
+        match self.grammar.rule(start) {
+            Some(rule) => Ok(self.rule(rule, input)),
+            None => Err(GrammarError::new(0, format!("undefined start rule `{}`", start))),
+        }
+    }
+
+    pub fn trace<I>(&self, start : &str, input : &mut I) -> Result<(TreeResult, Vec<TraceEvent>), GrammarError>
+        where I : MatchInput, I::Item : Into<u32> {
+
+        self.tracing.set(true);
+        self.depth.set(0);
+        let result = self.run(start, input);
+        self.tracing.set(false);
+        let steps = self.steps.take();
+        Ok((result?, steps))
+    }
+
+    fn step(&self, step : impl FnOnce() -> TraceEvent) {
+        if self.tracing.get() {
+            self.steps.borrow_mut().push(step());
+        }
+    }
+
+    fn rule<I>(&self, rule : &Rule, input : &mut I) -> Result<Success<Tree>, MatchError>
+        where I : MatchInput, I::Item : Into<u32> {
+
+        crate::limit::enter(input)?;
+        #[cfg(feature = "profile")]
+        crate::profile::enter(rule.name.clone());
+        let depth = self.depth.get();
+        self.step(|| TraceEvent::Enter { depth, matcher: rule.name.clone().into(), position: crate::peek_position(input) });
+        self.depth.set(depth + 1);
+        let result = self.alternatives(&rule.alternatives, input);
+        self.depth.set(depth);
+        #[cfg(feature = "profile")]
+        crate::profile::exit(&result);
+        crate::limit::exit();
+        match result {
+            Ok(s) => {
+                self.step(|| TraceEvent::Exit { depth, matcher: rule.name.clone().into(), result: Ok((s.start, s.end)) });
+                Ok(Success { item: Tree { rule: rule.name.clone(), start: s.start, end: s.end, children: s.item }, start: s.start, end: s.end })
+            },
+            Err(e) => {
+                self.step(|| TraceEvent::Exit { depth, matcher: rule.name.clone().into(), result: Err(format!("{:?}", e)) });
+                Err(e)
+            },
+        }
+    }
+
+    fn alternatives<I>(&self, alternatives : &[Alternative], input : &mut I) -> Result<Success<Vec<Tree>>, MatchError>
+        where I : MatchInput, I::Item : Into<u32> {
+
+        if let [alternative] = alternatives {
+            return self.sequence(alternative, input);
+        }
+        let rp = input.checkpoint();
+        let mut error = None;
+        for alternative in alternatives {
+            match self.sequence(alternative, input) {
+                Ok(s) => return Ok(s),
+                Err(e @ MatchError::Error(_)) | Err(e @ MatchError::ErrorEndOfFile) => {
+                    let position = match e {
+                        MatchError::Error(i) => Some(i),
+                        _ => None,
+                    };
+                    self.step(|| TraceEvent::Backtrack { depth: self.depth.get(), position });
+                    error = Some(e);
+                },
+                Err(MatchError::Incomplete(n)) => {
+                    input.restore(rp);
+                    return Err(MatchError::Incomplete(n));
+                },
+                Err(e) => return Err(e),
+            }
+        }
+        input.restore(rp);
+        Err(error.unwrap())
+    }
+
+    fn sequence<I>(&self, alternative : &Alternative, input : &mut I) -> Result<Success<Vec<Tree>>, MatchError>
+        where I : MatchInput, I::Item : Into<u32> {
+
+        let rp = input.checkpoint();
+        let mut children = vec![];
+        let mut start = 0;
+        let mut end = 0;
+        for (k, element) in alternative.elements.iter().enumerate() {
+            let s = match self.element(&element.expr, input, k != 0) {
+                Ok(s) => s,
+                Err(MatchError::Incomplete(n)) => {
+                    input.restore(rp);
+                    return Err(MatchError::Incomplete(n));
+                },
+                Err(e) => return Err(e),
+            };
+            if k == 0 {
+                start = s.start;
+                end = s.end;
+            }
+            else if end < s.end {
+                end = s.end;
+            }
+            children.extend(s.item);
+        }
+        Ok(Success { item: children, start, end })
+    }
+
+    fn element<I>(&self, expr : &Expr, input : &mut I, fatal : bool) -> Result<Success<Vec<Tree>>, MatchError>
+        where I : MatchInput, I::Item : Into<u32> {
+
+        let class = match expr {
+            Expr::Pattern(p) => &self.classes[p],
+            Expr::Any => &vec![(0, u32::MAX)],
+            _ => {
+                return match self.matcher(expr, input) {
+                    Err(MatchError::Error(i)) if fatal => Err(MatchError::Fatal(i)),
+                    Err(MatchError::ErrorEndOfFile) if fatal => Err(MatchError::FatalEndOfFile),
+                    r => r,
+                };
+            },
+        };
+        let rp = input.checkpoint();
+        match input.next_item() {
+            Some((i, item)) => {
+                let v = item.into();
+                if class.iter().any(|&(a, b)| a <= v && v <= b) {
+                    Ok(Success { item: vec![], start: i, end: i })
+                }
+                else {
+                    input.restore(rp);
+                    Err(if fatal { MatchError::Fatal(i) } else { MatchError::Error(i) })
+                }
+            },
+            None => {
+                let e = input.end_of_input(fatal);
+                input.restore(rp);
+                Err(e)
+            },
+        }
+    }
+
+    fn matcher<I>(&self, expr : &Expr, input : &mut I) -> Result<Success<Vec<Tree>>, MatchError>
+        where I : MatchInput, I::Item : Into<u32> {
+
+        match expr {
+            Expr::Rule(name) => {
+                let s = self.rule(self.grammar.rule(name).unwrap(), input)?;
+                Ok(Success { start: s.start, end: s.end, item: vec![s.item] })
+            },
+            Expr::Pattern(_) | Expr::Any => self.element(expr, input, false),
+            Expr::Pred(_) | Expr::Opaque(_) => unreachable!(),
+            Expr::Str(s) => {
+                let rp = input.checkpoint();
+                let mut end = 0;
+                let mut start = 0;
+                for (k, c) in s.chars().enumerate() {
+                    let fatal = k != 0;
+                    match input.next_item().map(|(i, item)| (i, item.into())) {
+                        Some((i, v)) if v == c as u32 => {
+                            if k == 0 {
+                                start = i;
+                            }
+                            end = i;
+                        },
+                        Some((i, _)) => {
+                            input.restore(rp);
+                            return Err(if fatal { MatchError::Fatal(i) } else { MatchError::Error(i) });
+                        },
+                        None => {
+                            let e = input.end_of_input(fatal);
+                            input.restore(rp);
+                            return Err(e);
+                        },
+                    }
+                }
+                Ok(Success { item: vec![], start, end })
+            },
+            Expr::Group(alternatives) => self.alternatives(alternatives, input),
+            Expr::Many(e) => {
+                let rp = if input.is_partial() { Some(input.checkpoint()) } else { None };
+                let mut ret = vec![];
+                let mut position = crate::peek_position(input);
+                let (start, mut end) = match crate::progress(self.element(e, input, false), input, &mut position) {
+                    Ok(s) => {
+                        ret.extend(s.item);
+                        (s.start, s.end)
+                    },
+                    Err(MatchError::Error(i)) => return Ok(Success { item: ret, start: i, end: i }),
+                    Err(MatchError::ErrorEndOfFile) => return Ok(Success { item: ret, start: 0, end: 0 }),
+                    Err(e) => return Err(e),
+                };
+                loop {
+                    let cp = input.checkpoint();
+                    match crate::progress(self.element(e, input, false), input, &mut position) {
+                        Ok(s) => {
+                            end = s.end;
+                            ret.extend(s.item);
+                        },
+                        Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => {
+                            input.restore(cp);
+                            break;
+                        },
+                        Err(MatchError::Incomplete(n)) => {
+                            if let Some(rp) = rp {
+                                input.restore(rp);
+                            }
+                            return Err(MatchError::Incomplete(n));
+                        },
+                        Err(e) => return Err(e),
+                    }
+                }
+                Ok(Success { item: ret, start, end })
+            },
+            Expr::Many1(e) => {
+                let first = self.element(e, input, false)?;
+                let rest = self.element(&Expr::Many(e.clone()), input, true)?;
+                let mut item = first.item;
+                item.extend(rest.item);
+                Ok(Success { item, start: first.start, end: std::cmp::max(first.end, rest.end) })
+            },
+            Expr::Optional(e) => match self.element(e, input, false) {
+                Err(MatchError::Error(i)) => Ok(Success { item: vec![], start: i, end: i }),
+                Err(MatchError::ErrorEndOfFile) => Ok(Success { item: vec![], start: 0, end: 0 }),
+                r => r,
+            },
+            Expr::And(e) => {
+                let at = crate::peek_position(input).unwrap_or(0);
+                let rp = input.checkpoint();
+                let result = self.matcher(e, input);
+                input.restore(rp);
+                match result {
+                    Ok(_) => Ok(Success { item: vec![], start: at, end: at }),
+                    Err(MatchError::Fatal(i)) => Err(MatchError::Error(i)),
+                    Err(MatchError::FatalEndOfFile) => Err(MatchError::ErrorEndOfFile),
+                    Err(e) => Err(e),
+                }
+            },
+            Expr::Not(e) => {
+                let at = crate::peek_position(input).unwrap_or(0);
+                let rp = input.checkpoint();
+                let result = self.matcher(e, input);
+                input.restore(rp);
+                match result {
+                    Ok(s) => Err(MatchError::Error(s.start)),
+                    Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) | Err(MatchError::Fatal(_)) | Err(MatchError::FatalEndOfFile) => Ok(Success { item: vec![], start: at, end: at }),
+                    Err(e) => Err(e),
+                }
+            },
+        }
+    }
+}
+
+pub fn compile(grammar : impl AsRef<Path>, out : impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let path = grammar.as_ref();
+    println!("cargo:rerun-if-changed={}", path.display());
+    let src = std::fs::read_to_string(path)?;
+    let grammar = Grammar::parse(&src).map_err(|e| format!("{}:{}: {}", path.display(), e.line, e.message))?;
+    for lint in grammar.lint() {
+        println!("cargo:warning={}: {}", path.display(), lint);
+    }
+    let rust = grammar.to_rust().map_err(|e| format!("{}:{}: {}", path.display(), e.line, e.message))?;
+    std::fs::write(out, format!("// generated from {}; do not edit\n\n{}", path.display(), rust))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_should_read_rules_and_elements() -> Result<(), GrammarError> {
+        let g = Grammar::parse(concat!(
+            "# arithmetic\n",
+            "@input char\n",
+            "number : u32 = d:[ '0'..='9' ]+ { d.into_iter().collect::<String>().parse().unwrap() } ;\n",
+            "sign : char = '+' / '-' ;\n",
+            "ws = (' ' / '\\t')* ;\n",
+        ))?;
+
+        assert_eq!( g.input, "char" );
+        assert_eq!( g.rules.len(), 3 );
+        assert_eq!( g.rules[0].ty, "u32" );
+        assert_eq!( g.rules[0].line, 3 );
+        assert_eq!( g.rules[0].alternatives[0].elements[0].label.as_deref(), Some("d") );
+        assert_eq!( g.rules[0].alternatives[0].elements[0].expr, Expr::Many1(Box::new(Expr::Pattern("'0'..='9'".into()))) );
+        assert_eq!( g.rules[1].alternatives.len(), 2 );
+        assert_eq!( g.rules[2].ty, "()" );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_should_report_line_numbers() {
+        let e = Grammar::parse("a = 'x' ;\n\nb = a c ;\n").unwrap_err();
+
+        assert_eq!( e, GrammarError::new(3, "undefined rule `c`") );
+
+        let e = Grammar::parse("a = 'x'\nb = 'y' ;\n").unwrap_err();
+
+        assert_eq!( e.line, 2 );
+
+        let e = Grammar::parse("a = 'x' ;\na = 'y' ;\n").unwrap_err();
+
+        assert_eq!( e, GrammarError::at(2, 1, "rule `a` is defined more than once") );
+
+        let e = Grammar::parse("a : u8 = 'x' {\n x\n").unwrap_err();
+
+        assert_eq!( e, GrammarError::at(1, 15, "unterminated action block") );
+    }
+
+    #[test]
+    fn to_rust_should_emit_crate_macros() -> Result<(), GrammarError> {
+        let g = Grammar::parse("sign : char = '+' / '-' ;\nkw = \"if\" !x ;\nx : char = [ 'a'..='z' ] ;\n")?;
+
+        let rust = g.to_rust()?;
+
+        assert!( rust.contains("seq!(__sign_0<'a> : char => char = _e0 <= '+', { _e0 });") );
+        assert!( rust.contains("alt!(sign<'a> : char => char = __sign_0 | __sign_1);") );
+        assert!( rust.contains("seq!(__kw_0<'a> : char => &'static str = _0 <= 'i', _1 <= 'f', { \"if\" });") );
+        assert!( rust.contains("let result = x(input);") );
+        assert!( rust.contains("Ok(Success { item: (), start: at, end: at })") );
+
+        let g = Grammar::parse("@input u8\nkw = \"i'\" ;\n")?;
+
+        assert!( g.to_rust()?.contains("= _0 <= b'\\x69', _1 <= b'\\x27', {") );
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_rust_should_require_actions_for_typed_rules() {
+        let g = Grammar::parse("a : u32 = 'x' ;\n").unwrap();
+
+        assert_eq!( g.to_rust().unwrap_err(), GrammarError::new(1, "rule `a` of type `u32` needs an action; `'x'` produces `char`") );
+
+        let g = Grammar::parse("a = 'x' ;\n\nb : u32 = a a ;\n").unwrap();
+
+        assert_eq!( g.to_rust().unwrap_err(), GrammarError::new(3, "rule `b` of type `u32` needs an action") );
+
+        let g = Grammar::parse("a : char = 'x' ;\nb : char = a ;\n").unwrap();
+
+        assert!( g.to_rust().is_ok() );
+    }
+
+    #[test]
+    fn parse_should_skip_multi_byte_char_literals_in_actions() -> Result<(), GrammarError> {
+        let g = Grammar::parse("a : bool = c:. { ['é', '}'].contains(&c) } ;\n")?;
+
+        assert_eq!( g.rules[0].alternatives[0].action.as_deref(), Some("['é', '}'].contains(&c)") );
+
+        Ok(())
+    }
+
+    #[test]
+    fn to_rust_should_reject_actions_in_groups() {
+        let g = Grammar::parse("a = ('x' { 1 })* ;\n").unwrap();
+
+        assert_eq!( g.to_rust().unwrap_err().line, 1 );
+    }
+
+    fn chars(s : &str) -> impl MatchInput<Item = u32> + '_ {
+        s.char_indices().map(|(i, c)| (i, c as u32))
+    }
+
+    #[test]
+    fn interpreter_should_build_a_tree() -> Result<(), MatchError> {
+        let g = Grammar::parse(concat!(
+            "digit : char = ['0'..='9'] ;\n",
+            "number : u32 = digit+ ;\n",
+            "list = number (',' number)* !. ;\n",
+        )).unwrap();
+        let interpreter = Interpreter::new(g).unwrap();
+
+        let o = interpreter.run("list", &mut chars("12,3")).unwrap()?;
+
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 3 );
+        assert_eq!( o.item.children.len(), 2 );
+        assert_eq!( o.item.children[1], Tree { rule: "number".into(), start: 3, end: 3, children: vec![Tree { rule: "digit".into(), start: 3, end: 3, children: vec![] }] } );
+
+        assert!( matches!( interpreter.run("list", &mut chars("x")).unwrap(), Err(MatchError::Error(0)) ) );
+        assert!( matches!( interpreter.run("list", &mut chars("1,x")).unwrap(), Err(MatchError::Fatal(2)) ) );
+        assert!( matches!( interpreter.run("list", &mut chars("1;")).unwrap(), Err(MatchError::Fatal(1)) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_should_match_strings_and_alternatives() -> Result<(), MatchError> {
+        let g = Grammar::parse("kw = \"let\" / \"in\" / ['x' | 'y'] / 0x7A ;\n").unwrap();
+        let interpreter = Interpreter::new(g).unwrap();
+
+        assert_eq!( interpreter.run("kw", &mut chars("in")).unwrap()?.end, 1 );
+        assert_eq!( interpreter.run("kw", &mut chars("y")).unwrap()?.end, 0 );
+        assert_eq!( interpreter.run("kw", &mut chars("z")).unwrap()?.end, 0 );
+        assert!( matches!( interpreter.run("kw", &mut chars("le")).unwrap(), Err(MatchError::FatalEndOfFile) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_should_place_lookaheads_at_the_current_position() -> Result<(), MatchError> {
+        let g = Grammar::parse("pair = word ' ' word ;\nword = !\"if\" &['a'..='z'] ['a'..='z']+ ;\n").unwrap();
+        let interpreter = Interpreter::new(g).unwrap();
+
+        let o = interpreter.run("pair", &mut chars("ab cd")).unwrap()?;
+
+        assert_eq!( (o.item.children[1].start, o.item.children[1].end), (3, 4) );
+        assert!( matches!( interpreter.run("pair", &mut chars("ab if")).unwrap(), Err(MatchError::Fatal(3)) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_should_stop_repetitions_that_do_not_advance() -> Result<(), MatchError> {
+        let g = Grammar::parse("as = ('a'?)* 'b' ;\n").unwrap();
+        let interpreter = Interpreter::new(g).unwrap();
+
+        assert_eq!( interpreter.run("as", &mut chars("aab")).unwrap()?.end, 2 );
+        assert_eq!( interpreter.run("as", &mut chars("b")).unwrap()?.end, 0 );
+        assert!( matches!( interpreter.run("as", &mut chars("c")).unwrap(), Err(MatchError::Fatal(0)) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_should_respect_limits() -> Result<(), MatchError> {
+        let g = Grammar::parse("nested = '(' nested ')' / 'x' ;\n").unwrap();
+        let interpreter = Interpreter::new(g).unwrap();
+
+        let o = crate::with_limits(crate::Limits::new().depth(3), || interpreter.run("nested", &mut chars("((((x))))")).unwrap());
+
+        assert!( matches!( o, Err(MatchError::LimitExceeded { limit: crate::Limit::Depth(3), position: Some(3) }) ) );
+
+        let o = crate::with_limits(crate::Limits::new().steps(5).depth(5), || interpreter.run("nested", &mut chars("((((x))))")).unwrap())?;
+
+        assert_eq!( o.end, 8 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn interpreter_should_reject_rust_only_constructs() {
+        let g = Grammar::parse("a : char = {? |c : char| c.is_alphabetic() } ;\n").unwrap();
+
+        assert_eq!( Interpreter::new(g).err().map(|e| e.line), Some(1) );
+
+        let g = Grammar::parse("\nb : char = [ c if c > 'a' ] ;\n").unwrap();
+
+        assert_eq!( Interpreter::new(g).err().map(|e| e.line), Some(2) );
+    }
+
+    #[test]
+    fn interpreter_should_report_undefined_start_rule() {
+        let g = Grammar::parse("a = 'x' ;\n").unwrap();
+        let interpreter = Interpreter::new(g).unwrap();
+
+        assert_eq!( interpreter.run("nope", &mut chars("x")).err(), Some(GrammarError::new(0, "undefined start rule `nope`")) );
+        assert!( interpreter.trace("nope", &mut chars("x")).is_err() );
+    }
+
+    #[test]
+    fn item_type_should_accept_only_chars_and_bytes() {
+        let mut g = Grammar::parse("@input &'a u8\na = 'x' ;\n").unwrap();
+
+        assert_eq!( g.item_type(), Ok(ItemType::Byte) );
+        assert_eq!( Interpreter::new(g.clone()).map(|i| i.item_type()), Ok(ItemType::Byte) );
+
+        g.input = "u16".to_string();
+
+        assert_eq!( g.item_type().unwrap_err().to_string(), "unsupported item type `u16`; expected `char` or `u8`" );
+        assert!( Interpreter::new(g.clone()).is_err() );
+
+        let g = Grammar { input: "u16".to_string(), ..Grammar::parse("a = \"xy\" ;\n").unwrap() };
+
+        assert_eq!( g.to_rust().unwrap_err().line, 1 );
+    }
+
+    #[test]
+    fn define_should_add_and_replace_rules() {
+        let mut g = Grammar::parse("a = 'x' ;\n").unwrap();
+
+        assert_eq!( g.define("b = a c ;"), Ok(vec!["b".to_string()]) );
+        assert!( g.check().is_err() );
+
+        g.define("c = 'z' ; a = 'y' ;").unwrap();
+
+        assert_eq!( g.check(), Ok(()) );
+        assert_eq!( g.rules.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), vec!["a", "b", "c"] );
+        assert_eq!( g.rules[0].alternatives[0].elements[0].expr, Expr::Pattern("'y'".into()) );
+    }
+
+    #[test]
+    fn trace_should_record_rule_entries_exits_and_backtracks() {
+        let g = Grammar::parse("s = a / b ;\na = 'x' 'y' ;\nb = 'x' ;\n").unwrap();
+        let interpreter = Interpreter::new(g).unwrap();
+
+        let (result, steps) = interpreter.trace("s", &mut chars("xz")).unwrap();
+
+        assert!( matches!( result, Err(MatchError::Fatal(1)) ) );
+        assert_eq!( steps, vec![
+            TraceEvent::Enter { depth: 0, matcher: "s".into(), position: Some(0) },
+            TraceEvent::Enter { depth: 1, matcher: "a".into(), position: Some(0) },
+            TraceEvent::Exit { depth: 1, matcher: "a".into(), result: Err("Fatal(1)".into()) },
+            TraceEvent::Exit { depth: 0, matcher: "s".into(), result: Err("Fatal(1)".into()) },
+        ] );
+
+        let (result, steps) = interpreter.trace("s", &mut chars("q")).unwrap();
+
+        assert!( result.is_err() );
+        assert_eq!( steps[2], TraceEvent::Exit { depth: 1, matcher: "a".into(), result: Err("Error(0)".into()) } );
+        assert_eq!( steps[3].to_string(), "  backtrack at 0" );
+
+        assert!( interpreter.run("s", &mut chars("xy")).unwrap().is_ok() );
+
+        let (result, steps) = interpreter.trace("b", &mut chars("x")).unwrap();
+
+        assert!( result.is_ok() );
+        assert_eq!( steps.len(), 2 );
+        assert_eq!( steps[1].to_string(), "b matched 0..=0" );
+    }
+
+    #[cfg(feature = "profile")]
+    #[test]
+    fn interpreter_should_report_rules_to_profile() {
+        let g = Grammar::parse("s = a / b ;\na = 'x' 'y' ;\nb = 'x' ;\n").unwrap();
+        let interpreter = Interpreter::new(g).unwrap();
+
+        let (_, report) = crate::profile(|| {
+            ["xy", "x", "q"].iter().map(|s| interpreter.run("s", &mut chars(s)).unwrap().is_ok()).collect::<Vec<_>>()
+        });
+
+        assert_eq!( report.rule("s").map(|r| (r.calls, r.successes, r.errors, r.fatals)), Some((3, 1, 1, 1)) );
+        assert_eq!( report.rule("a").map(|r| (r.calls, r.successes, r.errors, r.fatals)), Some((3, 1, 1, 1)) );
+        assert_eq!( report.rule("b").map(|r| (r.calls, r.errors)), Some((1, 1)) );
+    }
+
+    #[test]
+    fn split_top_should_respect_literals_and_nesting() {
+        assert_eq!( split_top("'|' | '\\'' | ('a' | 'b') | \"|\"", "|"), vec!["'|' ", " '\\'' ", " ('a' | 'b') ", " \"|\""] );
+        assert_eq!( split_top("a <= x::<'a>, b <= 'é', { f(1, 2) }", ","), vec!["a <= x::<'a>", " b <= 'é'", " { f(1, 2) }"] );
+    }
+}
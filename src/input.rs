@@ -0,0 +1,390 @@
+
+use crate::MatchError;
+
+pub trait MatchInput {
+    type Item;
+    type Checkpoint;
+
+    fn next_item(&mut self) -> Option<(usize, Self::Item)>;
+    fn checkpoint(&self) -> Self::Checkpoint;
+    fn restore(&mut self, checkpoint : Self::Checkpoint);
+
+    fn seek(&mut self, _position : usize) -> bool {
+        false
+    }
+
+    fn can_seek(&self) -> bool {
+        false
+    }
+
+    fn is_partial(&self) -> bool {
+        false
+    }
+
+    fn start_node(&mut self, _kind : &'static str) {
+    }
+
+    fn finish_node(&mut self, _matched : bool) {
+    }
+
+    fn end_of_input(&mut self, fatal : bool) -> MatchError {
+        if self.is_partial() {
+            MatchError::Incomplete(Some(1))
+        }
+        else if fatal {
+            MatchError::FatalEndOfFile
+        }
+        else {
+            MatchError::ErrorEndOfFile
+        }
+    }
+}
+
+impl<T, I> MatchInput for I where I : Iterator<Item = (usize, T)> + Clone {
+    type Item = T;
+    type Checkpoint = I;
+
+    fn next_item(&mut self) -> Option<(usize, T)> {
+        self.next()
+    }
+
+    fn checkpoint(&self) -> I {
+        self.clone()
+    }
+
+    fn restore(&mut self, checkpoint : I) {
+        *self = checkpoint;
+    }
+}
+
+#[derive(Debug)]
+pub struct Partial<I> {
+    input : I,
+}
+
+impl<I : MatchInput> Partial<I> {
+    pub fn new(input : I) -> Self {
+        Partial { input }
+    }
+
+    pub fn get_ref(&self) -> &I {
+        &self.input
+    }
+
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I : MatchInput> MatchInput for Partial<I> {
+    type Item = I::Item;
+    type Checkpoint = I::Checkpoint;
+
+    fn next_item(&mut self) -> Option<(usize, I::Item)> {
+        self.input.next_item()
+    }
+
+    fn checkpoint(&self) -> I::Checkpoint {
+        self.input.checkpoint()
+    }
+
+    fn restore(&mut self, checkpoint : I::Checkpoint) {
+        self.input.restore(checkpoint)
+    }
+
+    fn seek(&mut self, position : usize) -> bool {
+        self.input.seek(position)
+    }
+
+    fn can_seek(&self) -> bool {
+        self.input.can_seek()
+    }
+
+    fn is_partial(&self) -> bool {
+        true
+    }
+
+    fn start_node(&mut self, kind : &'static str) {
+        self.input.start_node(kind)
+    }
+
+    fn finish_node(&mut self, matched : bool) {
+        self.input.finish_node(matched)
+    }
+
+    fn end_of_input(&mut self, fatal : bool) -> MatchError {
+        match self.input.end_of_input(fatal) {
+            MatchError::ErrorEndOfFile | MatchError::FatalEndOfFile => MatchError::Incomplete(Some(1)),
+            e => e,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SliceInput<'a, T> {
+    data : &'a [T],
+    index : usize,
+    offset : usize,
+}
+
+impl<'a, T> SliceInput<'a, T> {
+    pub fn new(data : &'a [T]) -> Self {
+        SliceInput { data, index: 0, offset: 0 }
+    }
+
+    pub fn with_offset(data : &'a [T], offset : usize) -> Self {
+        SliceInput { data, index: 0, offset }
+    }
+
+    pub fn position(&self) -> usize {
+        self.offset + self.index
+    }
+
+    pub fn remaining(&self) -> &'a [T] {
+        &self.data[self.index..]
+    }
+}
+
+impl<'a, T> Clone for SliceInput<'a, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for SliceInput<'a, T> { }
+
+impl<'a, T> MatchInput for SliceInput<'a, T> {
+    type Item = &'a T;
+    type Checkpoint = usize;
+
+    fn next_item(&mut self) -> Option<(usize, &'a T)> {
+        let item = self.data.get(self.index)?;
+        let i = self.offset + self.index;
+        self.index += 1;
+        Some((i, item))
+    }
+
+    fn checkpoint(&self) -> usize {
+        self.position()
+    }
+
+    fn restore(&mut self, checkpoint : usize) {
+        assert!(checkpoint >= self.offset && checkpoint - self.offset <= self.data.len(), "checkpoint {} is outside of input", checkpoint);
+        self.index = checkpoint - self.offset;
+    }
+
+    fn seek(&mut self, position : usize) -> bool {
+        if position < self.offset || position - self.offset > self.data.len() {
+            return false;
+        }
+        self.index = position - self.offset;
+        true
+    }
+
+    fn can_seek(&self) -> bool {
+        true
+    }
+}
+
+impl<'a, T> From<&'a [T]> for SliceInput<'a, T> {
+    fn from(data : &'a [T]) -> Self {
+        SliceInput::new(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    struct Lexer {
+        text : Vec<char>,
+        index : usize,
+    }
+
+    impl MatchInput for Lexer {
+        type Item = char;
+        type Checkpoint = usize;
+
+        fn next_item(&mut self) -> Option<(usize, char)> {
+            let c = *self.text.get(self.index)?;
+            self.index += 1;
+            Some((self.index - 1, c))
+        }
+
+        fn checkpoint(&self) -> usize {
+            self.index
+        }
+
+        fn restore(&mut self, checkpoint : usize) {
+            self.index = checkpoint;
+        }
+    }
+
+    #[test]
+    fn custom_input_should_work_with_all_matchers() -> Result<(), MatchError> {
+        group!(g<'a>: char => Vec<char> = |input| {
+            pred!(digit<'a>: char => char = |c : char| c.is_ascii_digit());
+            seq!(letter<'a>: char => char = l <= 'a'..='z', { l });
+            alt!(either<'a>: char => char = digit | letter);
+            seq!(zero_or_more ~ main<'a>: char => char = c <= either, { c });
+
+            main(input)
+        });
+
+        let mut i = Lexer { text: "a1b2-".chars().collect(), index: 0 };
+
+        let o = g(&mut i)?;
+
+        assert_eq!( o.item, vec!['a', '1', 'b', '2'] );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 3 );
+        assert_eq!( i.next_item(), Some((4, '-')) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn custom_input_should_restore_on_failure() {
+        seq!(main<'a>: char => () = _a <= 'a', _b <= 'b', { () });
+
+        let mut i = Lexer { text: "ac".chars().collect(), index: 0 };
+
+        let o = main(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(1)) ) );
+        assert_eq!( i.index, 0 );
+    }
+
+    #[test]
+    fn slice_input_should_yield_items_with_indices() {
+        let v : Vec<u8> = vec![0x00, 0x01, 0x02];
+        let mut i = SliceInput::new(&v);
+
+        assert_eq!( i.next_item(), Some((0, &0x00)) );
+        assert_eq!( i.next_item(), Some((1, &0x01)) );
+        assert_eq!( i.next_item(), Some((2, &0x02)) );
+        assert_eq!( i.next_item(), None );
+    }
+
+    #[test]
+    fn slice_input_should_rewind_to_checkpoint() {
+        let v : Vec<u8> = vec![0x00, 0x01, 0x02];
+        let mut i = SliceInput::new(&v);
+
+        i.next_item();
+        let cp = i.checkpoint();
+        i.next_item();
+        i.next_item();
+
+        assert_eq!( i.remaining().len(), 0 );
+
+        i.restore(cp);
+
+        assert_eq!( i.position(), 1 );
+        assert_eq!( i.next_item(), Some((1, &0x01)) );
+    }
+
+    #[test]
+    fn slice_input_should_report_indices_from_offset() {
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = SliceInput::with_offset(&v, 10);
+
+        assert_eq!( i.next_item(), Some((10, &0x00)) );
+
+        let cp = i.checkpoint();
+
+        assert_eq!( i.next_item(), Some((11, &0x01)) );
+
+        i.restore(cp);
+
+        assert_eq!( i.position(), 11 );
+    }
+
+    #[test]
+    fn slice_input_should_work_with_seq() -> Result<(), MatchError> {
+        pred!(even<'a>: &'a u8 => &'a u8 = |x : &u8| x & 1 == 0);
+        seq!(zero_or_more ~ evens<'a>: &'a u8 => u8 = e <= even, { *e });
+        seq!(main<'a>: &'a u8 => (Vec<u8>, u8) = es <= evens, o <= 0x03, { (es, *o) });
+
+        let v : Vec<u8> = vec![0x00, 0x02, 0x04, 0x03, 0x05];
+        let mut i = SliceInput::new(&v);
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item.0, vec![0x00, 0x02, 0x04] );
+        assert_eq!( o.item.1, 0x03 );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 3 );
+        assert_eq!( i.position(), 4 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn slice_input_should_be_copy() {
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = SliceInput::new(&v);
+        let j = i;
+
+        i.next_item();
+
+        assert_eq!( i.position(), 1 );
+        assert_eq!( j.position(), 0 );
+    }
+
+    #[test]
+    fn slice_input_should_seek_within_slice() {
+        let v : Vec<u8> = vec![0x00, 0x01, 0x02];
+        let mut i = SliceInput::with_offset(&v, 10);
+
+        assert!( i.can_seek() );
+        assert!( i.seek(12) );
+        assert_eq!( i.next_item(), Some((12, &0x02)) );
+        assert!( i.seek(10) );
+        assert_eq!( i.next_item(), Some((10, &0x00)) );
+        assert!( !i.seek(14) );
+        assert!( !i.seek(9) );
+        assert_eq!( i.position(), 11 );
+    }
+
+    #[test]
+    fn partial_input_should_report_incomplete_and_rewind() {
+        seq!(main<'a>: &'a u8 => () = _a <= 0x00, _b <= 0x01, _c <= 0x02, { () });
+
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = Partial::new(SliceInput::new(&v));
+
+        let o = main(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(Some(1))) ) );
+        assert_eq!( i.get_ref().position(), 0 );
+    }
+
+    #[test]
+    fn partial_input_should_match_when_enough_is_available() -> Result<(), MatchError> {
+        seq!(main<'a>: &'a u8 => u8 = _a <= 0x00, b <= _, { *b });
+
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = Partial::new(SliceInput::new(&v));
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item, 0x01 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn slice_input_should_reset_on_failure() {
+        seq!(main<'a>: &'a u8 => () = _a <= 0x00, _b <= 0x01, { () });
+
+        let v : Vec<u8> = vec![0x00, 0xFF];
+        let mut i = SliceInput::new(&v);
+
+        let o = main(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(1)) ) );
+        assert_eq!( i.position(), 0 );
+    }
+}
@@ -0,0 +1,287 @@
+
+use crate::{MatchError, MatchInput, Success};
+
+pub fn skip_to_alignment<I : MatchInput>(input : &mut I, n : usize, pad : impl Fn(&I::Item) -> bool) -> Result<Success<usize>, MatchError> {
+    let rp = input.checkpoint();
+    let mut count : usize = 0;
+    let mut start : usize = 0;
+    let mut end : usize = 0;
+    loop {
+        let cp = input.checkpoint();
+        match input.next_item() {
+            Some((i, _)) if i.checked_rem(n).unwrap_or(0) == 0 => {
+                crate::reset(input, cp);
+                if count == 0 {
+                    start = i;
+                    end = i;
+                }
+                return Ok(Success { item: count, start, end });
+            },
+            Some((i, item)) if pad(&item) => {
+                if count == 0 {
+                    start = i;
+                }
+                end = i;
+                count += 1;
+            },
+            Some((i, _)) => {
+                crate::reset(input, rp);
+                return Err(if count == 0 { MatchError::Error(i) } else { MatchError::Fatal(i) });
+            },
+            None if count == 0 && !input.is_partial() => {
+                crate::reset(input, cp);
+                return Ok(Success { item: 0, start: 0, end: 0 });
+            },
+            None => {
+                let e = input.end_of_input(count != 0);
+                crate::reset(input, rp);
+                return Err(e);
+            },
+        }
+    }
+}
+
+pub fn padding<I : MatchInput>(input : &mut I, n : usize, pad : impl Fn(&I::Item) -> bool) -> Result<Success<()>, MatchError> {
+    let rp = input.checkpoint();
+    let mut start : usize = 0;
+    let mut end : usize = 0;
+    for k in 0..n {
+        match input.next_item() {
+            Some((i, item)) if pad(&item) => {
+                if k == 0 {
+                    start = i;
+                }
+                end = i;
+            },
+            Some((i, _)) => {
+                crate::reset(input, rp);
+                return Err(if k == 0 { MatchError::Error(i) } else { MatchError::Fatal(i) });
+            },
+            None => {
+                let e = input.end_of_input(k != 0);
+                crate::reset(input, rp);
+                return Err(e);
+            },
+        }
+    }
+    Ok(Success { item: (), start, end })
+}
+
+pub fn at_offset<I, T>(input : &mut I, offset : usize, matcher : impl FnOnce(&mut I) -> Result<Success<T>, MatchError>) -> Result<Success<T>, MatchError>
+    where I : MatchInput {
+
+    if !input.can_seek() {
+        return Err(MatchError::Unseekable(offset));
+    }
+    let rp = input.checkpoint();
+    if !input.seek(offset) {
+        let e = input.end_of_input(false);
+        crate::reset(input, rp);
+        return Err(e);
+    }
+    let result = matcher(input);
+    crate::reset(input, rp);
+    result
+}
+
+#[macro_export]
+macro_rules! align {
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty = $n:expr) => {
+        align!($matcher_name<$life> : $in_t = $n, _);
+    };
+
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty = $n:expr, $p:pat) => {
+        #[allow(clippy::extra_unused_lifetimes, unreachable_patterns, clippy::redundant_pattern)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<usize>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                $crate::skip_to_alignment(input, $n, |x| matches!(x, $p))
+            })
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! padding {
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty = $n:expr, $p:pat) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<()>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                $crate::padding(input, $n, |x| matches!(x, $p))
+            })
+        }
+    };
+}
+
+#[macro_export]
+macro_rules! at_offset {
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $offset:ident, $body:ident) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                let rp = input.checkpoint();
+                let offset = $offset(input)?;
+                match $crate::at_offset(input, offset.item as usize, |i| $body(i)) {
+                    Ok(s) => Ok(Success { start: offset.start, end: offset.end, item: s.item }),
+                    Err(MatchError::Error(i)) => Err(MatchError::Fatal(i)),
+                    Err(MatchError::ErrorEndOfFile) => Err(MatchError::FatalEndOfFile),
+                    Err(MatchError::Incomplete(n)) => {
+                        $crate::reset(input, rp);
+                        Err(MatchError::Incomplete(n))
+                    },
+                    Err(e) => Err(e),
+                }
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use crate::number::*;
+
+    #[test]
+    fn align_should_skip_to_boundary() -> Result<(), MatchError> {
+        align!(align4<'a> : u8 = 4, 0x00);
+        seq!(main<'a> : u8 => (u8, usize, u8) = a <= _, n <= align4, b <= _, { (a, n, b) });
+
+        let v : Vec<u8> = vec![0xAA, 0x00, 0x00, 0x00, 0xBB];
+        let mut i = v.into_iter().enumerate();
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item, (0xAA, 3, 0xBB) );
+        assert_eq!( o.end, 4 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn align_should_not_skip_when_aligned() -> Result<(), MatchError> {
+        align!(align4<'a> : u8 = 4);
+
+        let v : Vec<u8> = vec![0x01, 0x02, 0x03, 0x04, 0x05];
+        let mut i = v.into_iter().enumerate();
+
+        i.next();
+        i.next();
+        i.next();
+        i.next();
+
+        let o = align4(&mut i)?;
+
+        assert_eq!( o.item, 0 );
+        assert_eq!( i.next(), Some((4, 0x05)) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn zero_alignment_should_not_skip() -> Result<(), MatchError> {
+        align!(align0<'a> : u8 = 0);
+        padding!(pad0<'a> : u8 = 0, 0x00);
+
+        let v : Vec<u8> = vec![0x01, 0x00, 0x02];
+        let mut i = v.into_iter().enumerate();
+
+        i.next();
+
+        assert_eq!( align0(&mut i)?.item, 0 );
+        assert!( pad0(&mut i).is_ok() );
+        assert_eq!( i.next(), Some((1, 0x00)) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn align_should_fail_on_non_padding_value() {
+        align!(align8<'a> : &'a u8 = 8, 0x00);
+
+        let v : Vec<u8> = vec![0x01, 0x00, 0x00, 0x07, 0x00];
+        let mut i = SliceInput::new(&v);
+
+        i.next_item();
+
+        let o = align8(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(3)) ) );
+        assert_eq!( i.position(), 1 );
+    }
+
+    #[test]
+    fn padding_should_expect_n_values() -> Result<(), MatchError> {
+        padding!(pad<'a> : u8 = 3, 0xFF);
+        seq!(main<'a> : u8 => u8 = _p <= pad, b <= _, { b });
+
+        let v : Vec<u8> = vec![0xFF, 0xFF, 0xFF, 0x01];
+        let mut i = v.into_iter().enumerate();
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item, 0x01 );
+        assert_eq!( o.start, 0 );
+
+        let v : Vec<u8> = vec![0xFF, 0xFF, 0x00, 0x01];
+        let mut i = v.into_iter().enumerate();
+
+        assert!( matches!( main(&mut i), Err(MatchError::Fatal(2)) ) );
+
+        let v : Vec<u8> = vec![0xFF];
+        let mut i = v.into_iter().enumerate();
+
+        assert!( matches!( main(&mut i), Err(MatchError::FatalEndOfFile) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn at_offset_should_not_move_main_cursor() -> Result<(), MatchError> {
+        seq!(name<'a> : &'a u8 => (u8, u8) = a <= _, b <= _, { (*a, *b) });
+        at_offset!(name_ref<'a> : &'a u8 => (u8, u8) = be_u16, name);
+        seq!(entry<'a> : &'a u8 => ((u8, u8), u8) = n <= name_ref, f <= _, { (n, *f) });
+
+        let v : Vec<u8> = vec![0x00, 0x04, 0x07, 0xFF, b'h', b'i'];
+        let mut i = SliceInput::new(&v);
+
+        let o = entry(&mut i)?;
+
+        assert_eq!( o.item, ((b'h', b'i'), 0x07) );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 2 );
+        assert_eq!( i.position(), 3 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn at_offset_should_report_offset_outside_of_input() {
+        seq!(byte<'a> : &'a u8 => u8 = b <= _, { *b });
+
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = SliceInput::new(&v);
+
+        let o = at_offset(&mut i, 10, byte);
+
+        assert!( matches!( o, Err(MatchError::ErrorEndOfFile) ) );
+
+        let mut i = Partial::new(SliceInput::new(&v));
+
+        let o = at_offset(&mut i, 10, byte);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(_)) ) );
+    }
+
+    #[test]
+    fn at_offset_should_reject_inputs_that_cannot_seek() {
+        seq!(byte<'a> : u8 => u8 = b <= _, { b });
+
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = v.into_iter().enumerate();
+
+        let o = at_offset(&mut i, 1, byte);
+
+        assert!( matches!( o, Err(MatchError::Unseekable(1)) ) );
+        assert_eq!( i.next(), Some((0, 0x00)) );
+    }
+}
@@ -0,0 +1,298 @@
+
+use crate::{MatchError, MatchInput, Success};
+
+#[derive(Debug)]
+pub struct Lexeme<T> {
+    pub item : T,
+    pub start : usize,
+    pub end : usize,
+}
+
+#[derive(Debug)]
+pub struct Tokens<T> {
+    lexemes : Vec<Lexeme<T>>,
+}
+
+impl<T> Tokens<T> {
+    pub fn new(lexemes : Vec<Lexeme<T>>) -> Self {
+        Tokens { lexemes }
+    }
+
+    pub fn input(&self) -> TokenInput<'_, T> {
+        TokenInput { lexemes: self.lexemes.iter() }
+    }
+
+    pub fn lexemes(&self) -> &[Lexeme<T>] {
+        &self.lexemes
+    }
+
+    pub fn span(&self, start : usize) -> Option<(usize, usize)> {
+        let index = self.lexemes.binary_search_by_key(&start, |l| l.start).ok()?;
+        let l = &self.lexemes[index];
+        Some((l.start, l.end))
+    }
+
+    pub fn len(&self) -> usize {
+        self.lexemes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lexemes.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub struct TokenInput<'a, T> {
+    lexemes : std::slice::Iter<'a, Lexeme<T>>,
+}
+
+impl<'a, T> Clone for TokenInput<'a, T> {
+    fn clone(&self) -> Self {
+        TokenInput { lexemes: self.lexemes.clone() }
+    }
+}
+
+impl<'a, T> Iterator for TokenInput<'a, T> {
+    type Item = (usize, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.lexemes.next().map(|l| (l.start, &l.item))
+    }
+}
+
+#[doc(hidden)]
+pub fn lexer_attempt<I, T>(input : &mut I, start : usize, matcher : impl FnOnce(&mut I) -> Result<Success<T>, MatchError>) -> Result<Option<usize>, MatchError>
+    where I : MatchInput {
+
+    let rp = input.checkpoint();
+    match matcher(input) {
+        Ok(_) => {
+            let after = match input.next_item() {
+                Some((i, _)) => i,
+                None => usize::MAX,
+            };
+            crate::reset(input, rp);
+            Ok(if after > start { Some(after) } else { None })
+        },
+        Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => {
+            crate::reset(input, rp);
+            Ok(None)
+        },
+        Err(e) => {
+            crate::reset(input, rp);
+            Err(e)
+        },
+    }
+}
+
+#[macro_export]
+macro_rules! lexer {
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $token_t:ty = $($m:ident => $ctor:expr),+ $(; skip $($s:ident),+)?) => {
+        #[allow(clippy::extra_unused_lifetimes, unused_assignments, clippy::redundant_closure_call)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$crate::Tokens<$token_t>>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                let rp = input.checkpoint();
+                let mut lexemes : Vec<$crate::Lexeme<$token_t>> = vec![];
+                let mut first : Option<usize> = None;
+                let mut last : usize = 0;
+                loop {
+                    let cp = input.checkpoint();
+                    let start = match input.next_item() {
+                        Some((i, _)) => i,
+                        None => {
+                            $crate::reset(input, cp);
+                            break;
+                        },
+                    };
+                    $crate::reset(input, cp);
+
+                    let mut best : Option<(usize, usize)> = None;
+                    let mut fatal : Option<MatchError> = None;
+                    let mut rule : usize = 0;
+                    $(
+                        match $crate::lexer_attempt(input, start, |i| $m(i)) {
+                            Ok(Some(after)) => {
+                                if best.map_or(true, |(_, b)| after > b) {
+                                    best = Some((rule, after));
+                                }
+                            },
+                            Ok(None) => { },
+                            Err(e @ MatchError::Fatal(_)) | Err(e @ MatchError::FatalEndOfFile) => {
+                                fatal.get_or_insert(e);
+                            },
+                            Err(e) => {
+                                $crate::reset(input, rp);
+                                return Err(e);
+                            },
+                        }
+                        rule += 1;
+                    )+
+                    $($(
+                        match $crate::lexer_attempt(input, start, |i| $s(i)) {
+                            Ok(Some(after)) => {
+                                if best.map_or(true, |(_, b)| after > b) {
+                                    best = Some((rule, after));
+                                }
+                            },
+                            Ok(None) => { },
+                            Err(e @ MatchError::Fatal(_)) | Err(e @ MatchError::FatalEndOfFile) => {
+                                fatal.get_or_insert(e);
+                            },
+                            Err(e) => {
+                                $crate::reset(input, rp);
+                                return Err(e);
+                            },
+                        }
+                        rule += 1;
+                    )+)?
+
+                    let winner = match best {
+                        Some((winner, _)) => winner,
+                        None => {
+                            $crate::reset(input, rp);
+                            return Err(match fatal {
+                                Some(e) => e,
+                                None if first.is_none() => MatchError::Error(start),
+                                None => MatchError::Fatal(start),
+                            });
+                        },
+                    };
+
+                    rule = 0;
+                    $(
+                        if winner == rule {
+                            let s = $m(input)?;
+                            first.get_or_insert(s.start);
+                            last = s.end;
+                            lexemes.push($crate::Lexeme { item: ($ctor)(s.item), start: s.start, end: s.end });
+                        }
+                        rule += 1;
+                    )+
+                    $($(
+                        if winner == rule {
+                            let s = $s(input)?;
+                            first.get_or_insert(s.start);
+                            last = s.end;
+                        }
+                        rule += 1;
+                    )+)?
+                }
+
+                Ok(Success { item: $crate::Tokens::new(lexemes), start: first.unwrap_or(0), end: last })
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[derive(Debug, PartialEq)]
+    enum Token {
+        Let,
+        Ident(String),
+        Number(u64),
+        Plus,
+        Equal,
+    }
+
+    pred!(alpha<'a> : char => char = |c : char| c.is_ascii_alphabetic());
+    pred!(digit<'a> : char => char = |c : char| c.is_ascii_digit());
+    pred!(space<'a> : char => char = |c : char| c.is_whitespace());
+    seq!(zero_or_more ~ alphas<'a> : char => char = c <= alpha, { c });
+    seq!(zero_or_more ~ digits<'a> : char => char = c <= digit, { c });
+    seq!(zero_or_more ~ spaces<'a> : char => char = c <= space, { c });
+
+    seq!(keyword_let<'a> : char => () = _l <= 'l', _e <= 'e', _t <= 't', { () });
+    seq!(ident<'a> : char => String = a <= alpha, rest <= alphas, {
+        std::iter::once(a).chain(rest).collect()
+    });
+    seq!(number<'a> : char => u64 = d <= digit, rest <= digits, {
+        std::iter::once(d).chain(rest).collect::<String>().parse().unwrap()
+    });
+    seq!(plus<'a> : char => () = _p <= '+', { () });
+    seq!(equal<'a> : char => () = _e <= '=', { () });
+    seq!(ws<'a> : char => () = _s <= space, _rest <= spaces, { () });
+
+    lexer!(lex<'a> : char => Token = keyword_let => |_| Token::Let, ident => Token::Ident, number => Token::Number, plus => |_| Token::Plus, equal => |_| Token::Equal; skip ws);
+
+    #[test]
+    fn lexer_should_prefer_longest_match_then_first_rule() -> Result<(), MatchError> {
+        let v = "let letter = 12 + let";
+        let mut i = v.char_indices();
+
+        let o = lex(&mut i)?;
+        let tokens : Vec<&Token> = o.item.lexemes().iter().map(|l| &l.item).collect();
+
+        assert_eq!( tokens, vec![&Token::Let, &Token::Ident("letter".into()), &Token::Equal, &Token::Number(12), &Token::Plus, &Token::Let] );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 20 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn lexer_should_retain_source_spans() -> Result<(), MatchError> {
+        let v = "ab  123";
+        let mut i = v.char_indices();
+
+        let o = lex(&mut i)?;
+
+        assert_eq!( o.item.len(), 2 );
+        assert_eq!( o.item.span(0), Some((0, 1)) );
+        assert_eq!( o.item.span(4), Some((4, 6)) );
+        assert_eq!( o.item.span(2), None );
+
+        Ok(())
+    }
+
+    #[test]
+    fn lexer_should_report_unknown_input() {
+        let v = "a + ?";
+        let mut i = v.char_indices();
+
+        let o = lex(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(4)) ) );
+
+        let v = "?";
+        let mut i = v.char_indices();
+
+        let o = lex(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Error(0)) ) );
+    }
+
+    #[test]
+    fn tokens_should_feed_seq_rules() -> Result<(), MatchError> {
+        seq!(assign<'a> : &'a Token => (String, u64) = _l <= Token::Let, name <= Token::Ident(_), _e <= Token::Equal, value <= Token::Number(_), {
+            match (name, value) {
+                (Token::Ident(n), Token::Number(v)) => (n.clone(), *v),
+                _ => unreachable!(),
+            }
+        });
+
+        let v = "let x = 42";
+        let mut i = v.char_indices();
+
+        let tokens = lex(&mut i)?.item;
+        let mut t = tokens.input();
+
+        let o = assign(&mut t)?;
+
+        assert_eq!( o.item, ("x".to_string(), 42) );
+        assert_eq!( o.start, 0 );
+        assert_eq!( tokens.span(o.end), Some((8, 9)) );
+
+        let v = "let = 42";
+        let mut i = v.char_indices();
+
+        let tokens = lex(&mut i)?.item;
+        let mut t = tokens.input();
+
+        assert!( matches!( assign(&mut t), Err(MatchError::Fatal(4)) ) );
+
+        Ok(())
+    }
+}
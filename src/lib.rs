@@ -1,4 +1,51 @@
 
+mod bits;
+mod checksum;
+mod cst;
+mod diagram;
+mod event;
+mod frame;
+pub mod grammar;
+mod input;
+mod layout;
+mod lexer;
+mod limit;
+mod lint;
+pub mod number;
+mod pattern;
+#[cfg(feature = "profile")]
+mod profile;
+mod read;
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "trace")]
+mod trace;
+#[cfg(feature = "derive")]
+pub mod syntax;
+
+pub use bits::*;
+pub use checksum::*;
+pub use cst::*;
+pub use event::*;
+pub use frame::*;
+pub use input::*;
+pub use layout::*;
+pub use lexer::*;
+pub use limit::*;
+pub use lint::*;
+pub use pattern::*;
+#[cfg(feature = "profile")]
+pub use profile::*;
+pub use read::*;
+#[cfg(feature = "async")]
+pub use stream::*;
+#[cfg(feature = "trace")]
+pub use trace::*;
+#[cfg(feature = "derive")]
+pub use array_pattern_derive::Pattern;
+
+#[cfg(all(test, feature = "derive"))]
+extern crate self as array_pattern;
 
 #[derive(Debug)]
 pub enum MatchError {
@@ -6,6 +53,11 @@ pub enum MatchError {
     ErrorEndOfFile,
     Fatal(usize), 
     FatalEndOfFile,
+    Incomplete(Option<usize>),
+    Unseekable(usize),
+    Io(std::io::Error),
+    Checksum { expected : u64, actual : u64, start : usize, end : usize },
+    LimitExceeded { limit : Limit, position : Option<usize> },
 }
 
 #[derive(Debug)]
@@ -15,11 +67,123 @@ pub struct Success<T> {
     pub end : usize,
 }
 
+pub fn no_trivia<I : MatchInput>(_input : &mut I) -> Result<Success<()>, MatchError> {
+    Ok(Success { item: (), start: 0, end: 0 })
+}
+
+#[doc(hidden)]
+pub fn peek_position<I : MatchInput>(input : &mut I) -> Option<usize> {
+    let rp = input.checkpoint();
+    let position = input.next_item().map(|(i, _)| i);
+    input.restore(rp);
+    position
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn progress<I : MatchInput, T>(result : Result<Success<T>, MatchError>, input : &mut I, position : &mut Option<usize>) -> Result<Success<T>, MatchError> {
+    let s = result?;
+    let after = peek_position(input);
+    if after == *position {
+        return Err(MatchError::Error(s.start));
+    }
+    *position = after;
+    Ok(s)
+}
+
+#[doc(hidden)]
+#[inline]
+pub fn reset<I : MatchInput>(input : &mut I, checkpoint : I::Checkpoint) {
+    #[cfg(feature = "profile")]
+    let before = profile::before_reset(input);
+    input.restore(checkpoint);
+    #[cfg(feature = "profile")]
+    profile::after_reset(input, before);
+    #[cfg(feature = "trace")]
+    trace::backtrack(input);
+}
+
+#[doc(hidden)]
+#[cfg_attr(not(any(feature = "trace", feature = "profile")), allow(unused_variables))]
+pub fn instrument<I, T>(input : &mut I, matcher : &'static str, f : impl FnOnce(&mut I) -> Result<Success<T>, MatchError>) -> Result<Success<T>, MatchError>
+    where I : MatchInput {
+
+    limit::enter(input)?;
+    #[cfg(feature = "trace")]
+    let depth = trace::enter(input, matcher);
+    #[cfg(feature = "profile")]
+    profile::enter(matcher);
+    let result = f(input);
+    #[cfg(feature = "profile")]
+    profile::exit(&result);
+    #[cfg(feature = "trace")]
+    trace::exit(depth, matcher, &result);
+    limit::exit();
+    result
+}
+
+#[doc(hidden)]
+pub fn repeat<I, S, T>(input : &mut I, skip : impl Fn(&mut I) -> Result<Success<S>, MatchError>, matcher : impl Fn(&mut I) -> Result<Success<T>, MatchError>) -> Result<Success<Vec<T>>, MatchError>
+    where I : MatchInput {
+
+    let rp = if input.is_partial() { Some(input.checkpoint()) } else { None };
+    let mut ret = vec![];
+    let mut position = peek_position(input);
+
+    let (start, mut end) = match progress(matcher(input), input, &mut position) {
+        Ok(s) => {
+            ret.push(s.item);
+            (s.start, s.end)
+        },
+        Err(MatchError::Error(i)) => { return Ok(Success{ item: ret, start: i, end: i }); },
+        Err(MatchError::ErrorEndOfFile) => { return Ok(Success{ item: ret, start: 0, end: 0 }); },
+        Err(e) => { return Err(e); },
+    };
+
+    loop {
+        let cp = input.checkpoint();
+        let result = match skip(input) {
+            Ok(_) | Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => matcher(input),
+            Err(e) => Err(e),
+        };
+        match progress(result, input, &mut position) {
+            Ok(s) => {
+                end = s.end;
+                ret.push(s.item);
+            },
+            Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => {
+                reset(input, cp);
+                break;
+            },
+            Err(MatchError::Incomplete(n)) => {
+                if let Some(rp) = rp {
+                    reset(input, rp);
+                }
+                return Err(MatchError::Incomplete(n));
+            },
+            Err(e) => { return Err(e); },
+        }
+    }
+
+    Ok(Success{ item: ret, start, end })
+}
+
+#[doc(hidden)]
+pub fn optional<T>(result : Result<Success<T>, MatchError>) -> Result<Success<Option<T>>, MatchError> {
+    match result {
+        Ok(Success{ item, start, end }) => Ok(Success{ item: Some(item), start, end }),
+        Err(MatchError::Error(i)) => Ok(Success{ item: None, start: i, end: i }),
+        Err(MatchError::ErrorEndOfFile) => Ok(Success{ item: None, start: 0, end: 0 }),
+        Err(e) => Err(e),
+    }
+}
+
 #[macro_export]
 macro_rules! group { 
     ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = |$input:ident| $b:block) => {
-        fn $matcher_name<$life>($input : &mut (impl Iterator<Item = (usize, $in_t)> + Clone)) -> Result<Success<$out_t>, MatchError> {
-            $b
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>($input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+            $crate::instrument($input, stringify!($matcher_name), |$input| $b)
         }
     };
 }
@@ -27,19 +191,23 @@ macro_rules! group {
 #[macro_export]
 macro_rules! pred {
     ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $predicate:expr) => {
-        fn $matcher_name<$life>(input : &mut (impl Iterator<Item = (usize, $in_t)> + Clone)) -> Result<Success<$out_t>, MatchError> {
-            let mut rp = input.clone();
-            match input.next() {
-                Some((i, c)) if $predicate(c) => Ok(Success { start: i, end: i, item: c }),
-                Some((i, _)) => { 
-                    std::mem::swap(&mut rp, input);
-                    Err(MatchError::Error(i))
-                },
-                None => {
-                    std::mem::swap(&mut rp, input);
-                    Err(MatchError::ErrorEndOfFile)
-                },
-            } 
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                let rp = input.checkpoint();
+                match input.next_item() {
+                    Some((i, c)) if $predicate(c) => Ok(Success { start: i, end: i, item: c }),
+                    Some((i, _)) => { 
+                        $crate::reset(input, rp);
+                        Err(MatchError::Error(i))
+                    },
+                    None => {
+                        let e = input.end_of_input(false);
+                        $crate::reset(input, rp);
+                        Err(e)
+                    },
+                } 
+            })
         }
     };
 }
@@ -47,39 +215,80 @@ macro_rules! pred {
 #[macro_export]
 macro_rules! alt {
 
-    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($m:ident)|+) => {
-        fn $matcher_name<$life>(input : &mut (impl Iterator<Item = (usize, $in_t)> + Clone)) -> Result<Success<$out_t>, MatchError> {
+    (skip($skip:path) ~ $matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($m:ident)|+) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
 
-            let mut _error : Option<MatchError> = None;
+            $crate::node(input, stringify!($matcher_name), |input| {
+                let rp = input.checkpoint();
+                let mut _error : Option<MatchError> = None;
 
-            $(
-                match $m(input) {
-                    Ok(v) => { return Ok(v); },
-                    e @ Err(MatchError::Fatal(_)) => { return e; },
-                    e @ Err(MatchError::FatalEndOfFile) => { return e; },
-                    Err(e @ MatchError::Error(_)) => { _error = Some(e); },
-                    Err(e @ MatchError::ErrorEndOfFile) => { _error = Some(e); },
+                match $skip(input) {
+                    Ok(_) | Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => { },
+                    Err(MatchError::Incomplete(n)) => {
+                        $crate::reset(input, rp);
+                        return Err(MatchError::Incomplete(n));
+                    },
+                    Err(e) => { return Err(e); },
                 }
 
-            )*
+                $(
+                    match $m(input) {
+                        Ok(v) => { return Ok(v); },
+                        Err(e @ MatchError::Error(_)) => { _error = Some(e); },
+                        Err(e @ MatchError::ErrorEndOfFile) => { _error = Some(e); },
+                        Err(MatchError::Incomplete(n)) => {
+                            $crate::reset(input, rp);
+                            return Err(MatchError::Incomplete(n));
+                        },
+                        e @ Err(_) => { return e; },
+                    }
+
+                )*
         
-            Err(_error.unwrap())
+                $crate::reset(input, rp);
+                Err(_error.unwrap())
+            })
         }
     };
+
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($m:ident)|+) => {
+        alt!(skip($crate::no_trivia) ~ $matcher_name<$life> : $in_t => $out_t = $($m)|+);
+    };
 }
 
 #[macro_export]
 macro_rules! seq {
 
-    (err, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= $matcher:ident, $($rest:tt)*) => {
+    (trivia, $skip:path, $rp:ident, $input:ident) => {
+        match $skip($input) {
+            Ok(_) | Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => { },
+            Err(MatchError::Incomplete(n)) => {
+                $crate::reset($input, $rp);
+                return Err(MatchError::Incomplete(n));
+            },
+            Err(e) => return Err(e),
+        }
+    };
+
+    ($mode:ident, $skip:path, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= true, $($rest:tt)*) => {
+        seq!($mode, $skip, $rp, $input, $start, $end, $n <= (true), $($rest)*);
+    };
+
+    ($mode:ident, $skip:path, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= false, $($rest:tt)*) => {
+        seq!($mode, $skip, $rp, $input, $start, $end, $n <= (false), $($rest)*);
+    };
+
+    (err, $skip:path, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= $matcher:ident, $($rest:tt)*) => {
         let v = $matcher($input)?;
         let $n = v.item;
         $start = v.start;
         $end = v.end;
-        seq!(fatal, $rp, $input, $start, $end, $($rest)*);
+        seq!(fatal, $skip, $rp, $input, $start, $end, $($rest)*);
     };
 
-    (fatal, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= $matcher:ident, $($rest:tt)*) => {
+    (fatal, $skip:path, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= $matcher:ident, $($rest:tt)*) => {
+        seq!(trivia, $skip, $rp, $input);
         let $n = match $matcher($input) {
             Ok(v) => {
                 if $end < v.end {
@@ -87,127 +296,119 @@ macro_rules! seq {
                 }
                 v.item
             },
-            Err(MatchError::Fatal(i)) => return Err(MatchError::Fatal(i)),
             Err(MatchError::Error(i)) => return Err(MatchError::Fatal(i)),
-            Err(MatchError::FatalEndOfFile) => return Err(MatchError::FatalEndOfFile),
             Err(MatchError::ErrorEndOfFile) => return Err(MatchError::FatalEndOfFile),
+            Err(MatchError::Incomplete(n)) => {
+                $crate::reset($input, $rp);
+                return Err(MatchError::Incomplete(n));
+            },
+            Err(e) => return Err(e),
         };
-        seq!(fatal, $rp, $input, $start, $end, $($rest)*);
+        seq!(fatal, $skip, $rp, $input, $start, $end, $($rest)*);
     };
 
-    (err, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= $p:pat, $($rest:tt)*) => {
-        #[allow(unreachable_patterns)]
-        let $n = match $input.next() {
+    (err, $skip:path, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= $p:pat, $($rest:tt)*) => {
+        #[allow(unreachable_patterns, unused_parens, clippy::redundant_pattern)]
+        let $n = match $input.next_item() {
             Some((i, item @ $p)) => {
                 $start = i;
                 $end = i;
                 item
             },
             Some((i, _)) => {
-                std::mem::swap(&mut $rp, $input); 
+                $crate::reset($input, $rp);
                 return Err(MatchError::Error(i)); 
             },
             _ => { 
-                std::mem::swap(&mut $rp, $input); 
-                return Err(MatchError::ErrorEndOfFile); 
+                let e = $input.end_of_input(false);
+                $crate::reset($input, $rp);
+                return Err(e); 
             },
         };
-        seq!(fatal, $rp, $input, $start, $end, $($rest)*);
+        seq!(fatal, $skip, $rp, $input, $start, $end, $($rest)*);
     };
 
-    (fatal, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= $p:pat, $($rest:tt)*) => {
-        #[allow(unreachable_patterns)]
-        let $n = match $input.next() {
+    (fatal, $skip:path, $rp:ident, $input:ident, $start:ident, $end:ident, $n:ident <= $p:pat, $($rest:tt)*) => {
+        seq!(trivia, $skip, $rp, $input);
+        #[allow(unreachable_patterns, unused_parens, clippy::redundant_pattern)]
+        let $n = match $input.next_item() {
             Some((i, item @ $p)) => {
                 $end = i;
                 item
             },
             Some((i, _)) => {
-                std::mem::swap(&mut $rp, $input); 
+                $crate::reset($input, $rp);
                 return Err(MatchError::Fatal(i));  
             },
             _ => { 
-                std::mem::swap(&mut $rp, $input); 
-                return Err(MatchError::FatalEndOfFile);  
+                let e = $input.end_of_input(true);
+                $crate::reset($input, $rp);
+                return Err(e);  
             },
         };
-        seq!(fatal, $rp, $input, $start, $end, $($rest)*);
+        seq!(fatal, $skip, $rp, $input, $start, $end, $($rest)*);
     };
 
-    ($mode:ident, $rp:ident, $input:ident, $start:ident, $end:ident, $b:block) => {
+    ($mode:ident, $skip:path, $rp:ident, $input:ident, $start:ident, $end:ident, $b:block) => {
+        #[allow(clippy::unused_unit)]
         let item = $b;
-        return Ok( Success { start: $start, end: $end, item: item } );
+        return Ok( Success { start: $start, end: $end, item } );
     };
 
-    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($rest:tt)*) => {
-        fn $matcher_name<$life>(input : &mut (impl Iterator<Item = (usize, $in_t)> + Clone)) -> Result<Success<$out_t>, MatchError> {
-            let mut _rp = input.clone();
-            let mut _start : usize = 0;
-            let mut _end : usize = 0;
-            seq!(err, _rp, input, _start, _end, $($rest)*);
+    (skip($skip:path) ~ $matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($rest:tt)*) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+            $crate::node(input, stringify!($matcher_name), |input| {
+                let _rp = input.checkpoint();
+                let mut _start : usize = 0;
+                let mut _end : usize = 0;
+                seq!(err, $skip, _rp, input, _start, _end, $($rest)*);
+            })
         }
     };
 
-    (zero_or_more ~ $matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($rest:tt)*) => {
-        fn $matcher_name<$life>(input : &mut (impl Iterator<Item = (usize, $in_t)> + Clone)) -> Result<Success<Vec<$out_t>>, MatchError> {
+    ($matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($rest:tt)*) => {
+        seq!(skip($crate::no_trivia) ~ $matcher_name<$life> : $in_t => $out_t = $($rest)*);
+    };
+
+    (zero_or_more ~ skip($skip:path) ~ $matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($rest:tt)*) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<Vec<$out_t>>, MatchError> {
 
-            fn matcher<$life>(input : &mut (impl Iterator<Item = (usize, $in_t)> + Clone)) -> Result<Success<$out_t>, MatchError> {
-                let mut _rp = input.clone();
+            #[allow(clippy::extra_unused_lifetimes)]
+            fn matcher<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+                let _rp = input.checkpoint();
                 let mut _start : usize = 0;
                 let mut _end : usize = 0;
-                seq!(err, _rp, input, _start, _end, $($rest)*);
+                seq!(err, $skip, _rp, input, _start, _end, $($rest)*);
             }
 
-            let mut ret = vec![];
-
-            let mut result = matcher(input);
-            let mut _start = 0;
-            let mut _end = 0;
-            match result {
-                Ok(s) => { 
-                    _start = s.start;
-                    _end = s.end;
-                    ret.push(s.item);
-                },
-                Err(MatchError::Error(i)) => { return Ok(Success{ item: ret, start: i, end: i }); },
-                Err(MatchError::ErrorEndOfFile) => { return Ok(Success{ item: ret, start: 0, end: 0 }); },
-                Err(MatchError::Fatal(i)) => { return Err(MatchError::Fatal(i)); },
-                Err(MatchError::FatalEndOfFile) => { return Err(MatchError::FatalEndOfFile); },
-            }
+            $crate::node(input, stringify!($matcher_name), |input| $crate::repeat(input, $skip, matcher))
+        }
+    };
 
-            loop {
-                result = matcher(input);
-                match result {
-                    Ok(s) => { 
-                        _end = s.end;
-                        ret.push(s.item);
-                    },
-                    Err(MatchError::Error(_)) => { break; },
-                    Err(MatchError::ErrorEndOfFile) => { break; },
-                    Err(MatchError::Fatal(i)) => { return Err(MatchError::Fatal(i)); },
-                    Err(MatchError::FatalEndOfFile) => { return Err(MatchError::FatalEndOfFile); },
-                }
+    (zero_or_more ~ $matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($rest:tt)*) => {
+        seq!(zero_or_more ~ skip($crate::no_trivia) ~ $matcher_name<$life> : $in_t => $out_t = $($rest)*);
+    };
+
+    (maybe ~ skip($skip:path) ~ $matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($rest:tt)*) => {
+        #[allow(clippy::extra_unused_lifetimes)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<Option<$out_t>>, MatchError> {
+
+            #[allow(clippy::extra_unused_lifetimes)]
+            fn matcher<$life>(input : &mut impl MatchInput<Item = $in_t>) -> Result<Success<$out_t>, MatchError> {
+                let _rp = input.checkpoint();
+                let mut _start : usize = 0;
+                let mut _end : usize = 0;
+                seq!(err, $skip, _rp, input, _start, _end, $($rest)*);
             }
 
-            Ok(Success{ item: ret, start: _start, end: _end })
+            $crate::node(input, stringify!($matcher_name), |input| $crate::optional(matcher(input)))
         }
     };
 
     (maybe ~ $matcher_name:ident<$life:lifetime> : $in_t:ty => $out_t:ty = $($rest:tt)*) => {
-        fn $matcher_name<$life>(input : &mut (impl Iterator<Item = (usize, $in_t)> + Clone)) -> Result<Success<Option<$out_t>>, MatchError> {
-            let mut _rp = input.clone();
-            let mut _start : usize = 0;
-            let mut _end : usize = 0;
-            let mut matcher = || { seq!(err, _rp, input, _start, _end, $($rest)*); };
-            let result = matcher();
-            match result {
-                Ok(Success{ item, start, end }) => Ok(Success{ item: Some(item), start, end }),
-                Err(MatchError::Error(i)) => Ok(Success{ item: None, start: i, end: i }),
-                Err(MatchError::ErrorEndOfFile) => Ok(Success{ item: None, start: 0, end: 0 }),
-                Err(MatchError::Fatal(i)) => Err(MatchError::Fatal(i)),
-                Err(MatchError::FatalEndOfFile) => Err(MatchError::FatalEndOfFile),
-            }
-        }
+        seq!(maybe ~ skip($crate::no_trivia) ~ $matcher_name<$life> : $in_t => $out_t = $($rest)*);
     };
 }
 
@@ -588,6 +789,31 @@ mod tests {
     }
 
     #[test]
+    fn zero_or_more_should_stop_when_body_does_not_consume() -> Result<(), MatchError> {
+        seq!(maybe ~ a<'a> : u8 => u8 = a <= 0x00, { a });
+        seq!(zero_or_more ~ something<'a> : u8 => Option<u8> = a <= a, { a });
+
+        let v : Vec<u8> = vec![0x00, 0x00, 0xFF];
+        let mut i = v.into_iter().enumerate();
+
+        let o = something(&mut i)?;
+
+        assert_eq!( o.item, vec![Some(0x00), Some(0x00)] );
+        assert_eq!( o.end, 1 );
+        assert_eq!( i.next(), Some((2, 0xFF)) );
+
+        let v : Vec<u8> = vec![0xFF];
+        let mut i = v.into_iter().enumerate();
+
+        let o = something(&mut i)?;
+
+        assert_eq!( o.item.len(), 0 );
+        assert_eq!( i.next(), Some((0, 0xFF)) );
+        Ok(())
+    }
+
+    #[test]
+    #[allow(clippy::redundant_pattern_matching)]
     fn maybe_should_handle_call_from_other_matcher() -> Result<(), MatchError> {
         struct Output {
             a : Option<u8>,
@@ -642,6 +868,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::redundant_pattern_matching)]
     fn seq_other_matcher_resets_iterator_on_failure() -> Result<(), MatchError> {
         seq!(other<'a>: u8 => () = _a <= _, _b <= _, _c <= _, _d <= 0xFF, {
             ()
@@ -669,6 +896,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(dead_code)]
     fn seq_other_matcher_error_eof_as_fatal_eof() {
         struct A(u8, u8);
         struct Main(A, A);
@@ -689,6 +917,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(dead_code)]
     fn seq_other_matcher_error_as_fatal() {
         struct A(u8, u8);
         struct Main(A, A);
@@ -709,6 +938,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(dead_code)]
     fn seq_other_matcher_fatal_eof_as_fatal_eof() {
         struct A(u8, u8);
         struct Main(A, A);
@@ -729,6 +959,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(dead_code)]
     fn seq_other_matcher_fatal_as_fatal() {
         struct A(u8, u8);
         struct Main(A, A);
@@ -749,6 +980,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(dead_code)]
     fn seq_other_matcher_error_eof_as_error_eof() {
         struct A(u8, u8);
         struct Main(A, A);
@@ -769,6 +1001,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(dead_code)]
     fn seq_other_matcher_error_as_error() {
         struct A(u8, u8);
         struct Main(A, A);
@@ -995,6 +1228,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn seq_should_handle_single_item_match() {
         enum Input {
             A, 
@@ -1025,6 +1259,7 @@ mod tests {
 
 
     #[test]
+    #[allow(clippy::useless_vec)]
     fn seq_should_handle_multiple_item_match() {
         enum Input {
             A, 
@@ -1064,6 +1299,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::redundant_field_names, clippy::useless_vec)]
     fn seq_should_handle_owned_item_match() {
         enum Input {
             A, 
@@ -1185,4 +1421,157 @@ mod tests {
         assert_eq!( output.end, 1 );
         Ok(())
     }
+
+    #[test]
+    fn pred_should_indicate_incomplete_on_partial_input() {
+        pred!(a<'a>: u8 => u8 = |x| x == 0x00);
+
+        let v : Vec<u8> = vec![];
+        let mut i = Partial::new(v.into_iter().enumerate());
+
+        let o = a(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(Some(1))) ) );
+    }
+
+    #[test]
+    fn alt_should_indicate_incomplete_if_incomplete() {
+        seq!(a<'a> : u8 => () = _o <= 0x00, _x <= 0x00, { () });
+        seq!(b<'a> : u8 => () = _o <= 0xFF, { () });
+
+        alt!(c<'a> : u8 => () = a | b);
+
+        let v : Vec<u8> = vec![0x00];
+        let mut i = Partial::new(v.into_iter().enumerate());
+
+        let o = c(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(_)) ) );
+    }
+
+    #[test]
+    fn seq_other_matcher_incomplete_should_rewind() -> Result<(), MatchError> {
+        seq!(other<'a>: u8 => () = _a <= 0x01, _b <= 0x02, { () });
+        seq!(main<'a>: u8 => () = _a <= 0x00, _b <= other, { () });
+        seq!(single<'a>: u8 => u8 = a <= _, { a });
+
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = Partial::new(v.into_iter().enumerate());
+
+        let o = main(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(Some(1))) ) );
+
+        let o = single(&mut i)?;
+
+        assert_eq!( o.item, 0x00 );
+        Ok(())
+    }
+
+    #[test]
+    fn zero_or_more_should_indicate_incomplete_and_rewind() -> Result<(), MatchError> {
+        seq!(zero_or_more ~ zeros<'a> : u8 => u8 = a <= 0x00, { a });
+        seq!(single<'a>: u8 => u8 = a <= _, { a });
+
+        let v : Vec<u8> = vec![0x00, 0x00];
+        let mut i = Partial::new(v.into_iter().enumerate());
+
+        let o = zeros(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(Some(1))) ) );
+
+        let o = single(&mut i)?;
+
+        assert_eq!( o.start, 0 );
+        Ok(())
+    }
+
+    #[test]
+    fn maybe_should_indicate_incomplete_on_partial_input() {
+        seq!(maybe ~ something<'a> : u8 => u8 = a <= 0xFF, { a });
+
+        let v : Vec<u8> = vec![];
+        let mut i = Partial::new(v.into_iter().enumerate());
+
+        let o = something(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(_)) ) );
+    }
+
+    pred!(space<'a> : char => char = |c : char| c.is_whitespace());
+    seq!(zero_or_more ~ ws<'a> : char => char = c <= space, { c });
+
+    #[test]
+    fn seq_should_skip_trivia_between_items() -> Result<(), MatchError> {
+        seq!(skip(ws) ~ pair<'a> : char => (char, char) = a <= 'a', _c <= ',', b <= 'b', { (a, b) });
+
+        let v = "a ,\n  b ";
+        let mut i = v.char_indices();
+
+        let o = pair(&mut i)?;
+
+        assert_eq!( o.item, ('a', 'b') );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 6 );
+        assert_eq!( i.next(), Some((7, ' ')) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn seq_without_skip_should_keep_trivia_significant() {
+        seq!(skip(ws) ~ pair<'a> : char => (char, char) = a <= 'a', b <= quoted, { (a, b) });
+        seq!(quoted<'a> : char => char = _o <= '"', c <= _, _e <= '"', { c });
+
+        let v = "a \"x\"";
+        let mut i = v.char_indices();
+
+        assert_eq!( pair(&mut i).map(|o| o.item).ok(), Some(('a', 'x')) );
+
+        let v = "a \" x\"";
+        let mut i = v.char_indices();
+
+        assert!( matches!( pair(&mut i), Err(MatchError::Fatal(4)) ) );
+    }
+
+    #[test]
+    fn alt_should_skip_trivia_before_alternatives() -> Result<(), MatchError> {
+        seq!(a<'a> : char => char = c <= 'a', { c });
+        seq!(b<'a> : char => char = c <= 'b', { c });
+        alt!(skip(ws) ~ a_or_b<'a> : char => char = a | b);
+
+        let v = "  b";
+        let mut i = v.char_indices();
+
+        let o = a_or_b(&mut i)?;
+
+        assert_eq!( o.item, 'b' );
+        assert_eq!( o.start, 2 );
+        assert_eq!( o.end, 2 );
+
+        let v = "  c";
+        let mut i = v.char_indices();
+
+        assert!( matches!( a_or_b(&mut i), Err(MatchError::Error(2)) ) );
+        assert_eq!( i.next(), Some((0, ' ')) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn zero_or_more_should_skip_trivia_between_repetitions() -> Result<(), MatchError> {
+        seq!(zero_or_more ~ skip(ws) ~ digits<'a> : char => char = d <= '0'..='9', { d });
+
+        let v = "1 2\t3 x";
+        let mut i = v.char_indices();
+
+        let o = digits(&mut i)?;
+
+        assert_eq!( o.item, vec!['1', '2', '3'] );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 4 );
+        assert_eq!( i.next(), Some((5, ' ')) );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,205 @@
+
+use std::cell::Cell;
+use std::fmt;
+
+use crate::{MatchError, MatchInput};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Steps(usize),
+    Depth(usize),
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Steps(max) => write!(f, "step limit of {} exceeded", max),
+            Limit::Depth(max) => write!(f, "depth limit of {} exceeded", max),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub steps : Option<usize>,
+    pub depth : Option<usize>,
+}
+
+impl Limits {
+    pub fn new() -> Self {
+        Limits::default()
+    }
+
+    pub fn steps(mut self, max : usize) -> Self {
+        self.steps = Some(max);
+        self
+    }
+
+    pub fn depth(mut self, max : usize) -> Self {
+        self.depth = Some(max);
+        self
+    }
+
+    fn is_unlimited(&self) -> bool {
+        self.steps.is_none() && self.depth.is_none()
+    }
+}
+
+thread_local! {
+    static LIMITS : Cell<Limits> = const { Cell::new(Limits { steps: None, depth: None }) };
+    static STEPS : Cell<usize> = const { Cell::new(0) };
+    static DEPTH : Cell<usize> = const { Cell::new(0) };
+}
+
+struct Restore {
+    limits : Limits,
+    steps : usize,
+    depth : usize,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        DEPTH.with(|d| d.set(self.depth));
+        STEPS.with(|s| s.set(self.steps));
+        LIMITS.with(|l| l.set(self.limits));
+    }
+}
+
+pub fn with_limits<R>(limits : Limits, f : impl FnOnce() -> R) -> R {
+    let _restore = Restore {
+        limits: LIMITS.with(|l| l.replace(limits)),
+        steps: STEPS.with(|s| s.replace(0)),
+        depth: DEPTH.with(|d| d.replace(0)),
+    };
+    f()
+}
+
+pub(crate) fn enter<I : MatchInput>(input : &mut I) -> Result<(), MatchError> {
+    let limits = LIMITS.with(|l| l.get());
+    if limits.is_unlimited() {
+        return Ok(());
+    }
+    let steps = STEPS.with(|s| s.get()) + 1;
+    if let Some(max) = limits.steps.filter(|&max| steps > max) {
+        return Err(MatchError::LimitExceeded { limit: Limit::Steps(max), position: crate::peek_position(input) });
+    }
+    let depth = DEPTH.with(|d| d.get()) + 1;
+    if let Some(max) = limits.depth.filter(|&max| depth > max) {
+        return Err(MatchError::LimitExceeded { limit: Limit::Depth(max), position: crate::peek_position(input) });
+    }
+    STEPS.with(|s| s.set(steps));
+    DEPTH.with(|d| d.set(depth));
+    Ok(())
+}
+
+pub(crate) fn exit() {
+    if !LIMITS.with(|l| l.get()).is_unlimited() {
+        DEPTH.with(|d| d.set(d.get().saturating_sub(1)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::number::*;
+
+    seq!(digit<'a> : char => char = d <= '0'..='9', { d });
+    seq!(zero_or_more ~ digits<'a> : char => char = d <= digit, { d });
+    seq!(inner<'a> : char => () = _o <= '(', _n <= nested, _c <= ')', { () });
+    seq!(leaf<'a> : char => () = _x <= 'x', { () });
+    alt!(nested<'a> : char => () = inner | leaf);
+
+    #[test]
+    fn limits_should_not_apply_by_default() -> Result<(), MatchError> {
+        let v = "((((x))))";
+
+        assert_eq!( nested(&mut v.char_indices())?.end, 8 );
+        assert_eq!( digits(&mut "0123456789".char_indices())?.item.len(), 10 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn step_limit_should_abort_with_position() -> Result<(), MatchError> {
+        let o = with_limits(Limits::new().steps(5), || digits(&mut "0123456789".char_indices()));
+
+        assert!( matches!( o, Err(MatchError::LimitExceeded { limit: Limit::Steps(5), position: Some(4) }) ) );
+
+        let o = with_limits(Limits::new().steps(20), || digits(&mut "0123456789".char_indices()))?;
+
+        assert_eq!( o.item.len(), 10 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn depth_limit_should_not_be_recoverable_by_alternatives() -> Result<(), MatchError> {
+        let v = "((((x))))";
+        let o = with_limits(Limits::new().depth(6), || nested(&mut v.char_indices()));
+
+        assert!( matches!( o, Err(MatchError::LimitExceeded { limit: Limit::Depth(6), position: Some(3) }) ) );
+
+        let o = with_limits(Limits::new().depth(10), || nested(&mut v.char_indices()))?;
+
+        assert_eq!( o.end, 8 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn limits_should_apply_to_group_and_pred_matchers() {
+        pred!(open<'a> : char => char = |c : char| c == '(');
+        group!(deep<'a> : char => usize = |input| {
+            match open(input) {
+                Ok(_) => deep(input).map(|s| Success { item: s.item + 1, ..s }),
+                Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => Ok(Success { item: 0, start: 0, end: 0 }),
+                Err(e) => Err(e),
+            }
+        });
+
+        let o = with_limits(Limits::new().depth(5), || deep(&mut "((((((((".char_indices()));
+
+        assert!( matches!( o, Err(MatchError::LimitExceeded { limit: Limit::Depth(5), position: Some(4) }) ) );
+
+        let o = with_limits(Limits::new().steps(3), || deep(&mut "((((((((".char_indices()));
+
+        assert!( matches!( o, Err(MatchError::LimitExceeded { limit: Limit::Steps(3), .. }) ) );
+    }
+
+    #[test]
+    fn limits_should_apply_to_binary_matchers() -> Result<(), MatchError> {
+        bits!(nibble<'a> : bool => u8 = 4);
+        length_prefixed!(frame<'a> : &'a u8 => u16 = length, be_u16);
+        seq!(length<'a> : &'a u8 => u8 = n <= _, { *n });
+
+        let v : Vec<u8> = vec![0x02, 0x01, 0x02];
+        let o = with_limits(Limits::new().steps(2), || frame(&mut SliceInput::new(&v)));
+
+        assert!( matches!( o, Err(MatchError::LimitExceeded { limit: Limit::Steps(2), position: Some(1) }) ) );
+
+        let o = with_limits(Limits::new().depth(1), || frame(&mut SliceInput::new(&v)));
+
+        assert!( matches!( o, Err(MatchError::LimitExceeded { limit: Limit::Depth(1), position: Some(0) }) ) );
+
+        let o = with_limits(Limits::new().steps(3), || frame(&mut SliceInput::new(&v)))?;
+
+        assert_eq!( o.item, 0x0102 );
+
+        let w : Vec<u8> = vec![0xAB];
+        let o = with_limits(Limits::new().steps(0), || nibble(&mut BitInput::new(&w)));
+
+        assert!( matches!( o, Err(MatchError::LimitExceeded { limit: Limit::Steps(0), position: Some(0) }) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn with_limits_should_restore_limits_after_panic() -> Result<(), MatchError> {
+        let o = std::panic::catch_unwind(|| with_limits(Limits::new().steps(1), || -> () { panic!("matcher panicked") }));
+
+        assert!( o.is_err() );
+        assert_eq!( digits(&mut "0123456789".char_indices())?.item.len(), 10 );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,339 @@
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use crate::grammar::{class, Alternative, Expr, Grammar};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lint {
+    ShadowedAlternative { rule : String, alternative : usize, by : usize, nullable : bool },
+    OverlappingAlternatives { rule : String, alternative : usize, with : usize },
+    NullableRepetition { rule : String, body : String },
+    LeftRecursion { rule : String, cycle : Vec<String> },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::ShadowedAlternative { rule, alternative, by, nullable: true } =>
+                write!(f, "rule `{}`: alternative {} is unreachable because alternative {} can match empty input", rule, alternative, by),
+            Lint::ShadowedAlternative { rule, alternative, by, nullable: false } =>
+                write!(f, "rule `{}`: alternative {} is unreachable because alternative {} matches a prefix of it", rule, alternative, by),
+            Lint::OverlappingAlternatives { rule, alternative, with } =>
+                write!(f, "rule `{}`: alternatives {} and {} can start with the same item; once {} matches it, {} is never tried", rule, with, alternative, with, alternative),
+            Lint::NullableRepetition { rule, body } =>
+                write!(f, "rule `{}`: repetition body `{}` can match empty input", rule, body),
+            Lint::LeftRecursion { rule, cycle } =>
+                write!(f, "rule `{}` is left-recursive: {}", rule, cycle.join(" -> ")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Terminal {
+    Class(Vec<(u32, u32)>),
+    Opaque(String),
+}
+
+impl Terminal {
+    fn pattern(p : &str) -> Terminal {
+        match class(p) {
+            Some(ranges) => Terminal::Class(ranges),
+            None => Terminal::Opaque(p.trim().to_string()),
+        }
+    }
+
+    fn any(&self) -> bool {
+        matches!(self, Terminal::Class(ranges) if ranges.iter().any(|&(a, b)| a == 0 && b == u32::MAX))
+    }
+
+    fn overlaps(&self, other : &Terminal) -> bool {
+        if self.any() || other.any() {
+            return true;
+        }
+        match (self, other) {
+            (Terminal::Class(a), Terminal::Class(b)) => a.iter().any(|&(a0, a1)| b.iter().any(|&(b0, b1)| a0 <= b1 && b0 <= a1)),
+            (Terminal::Opaque(a), Terminal::Opaque(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+struct Analysis<'g> {
+    grammar : &'g Grammar,
+    nullable : HashMap<&'g str, bool>,
+    first : HashMap<&'g str, BTreeSet<Terminal>>,
+}
+
+impl<'g> Analysis<'g> {
+    fn new(grammar : &'g Grammar) -> Analysis<'g> {
+        let mut analysis = Analysis {
+            grammar,
+            nullable : grammar.rules.iter().map(|r| (r.name.as_str(), false)).collect(),
+            first : grammar.rules.iter().map(|r| (r.name.as_str(), BTreeSet::new())).collect(),
+        };
+        loop {
+            let mut changed = false;
+            for rule in &grammar.rules {
+                let nullable = rule.alternatives.iter().any(|a| analysis.alternative_nullable(a));
+                let first = analysis.alternatives_first(&rule.alternatives);
+                if nullable != analysis.nullable[rule.name.as_str()] || first.len() != analysis.first[rule.name.as_str()].len() {
+                    changed = true;
+                }
+                analysis.nullable.insert(&rule.name, nullable);
+                analysis.first.insert(&rule.name, first);
+            }
+            if !changed {
+                return analysis;
+            }
+        }
+    }
+
+    fn nullable(&self, expr : &Expr) -> bool {
+        match expr {
+            Expr::Rule(name) => self.nullable.get(name.as_str()).copied().unwrap_or(false),
+            Expr::Pattern(_) | Expr::Any | Expr::Pred(_) | Expr::Opaque(_) => false,
+            Expr::Str(s) => s.is_empty(),
+            Expr::Group(alternatives) => alternatives.iter().any(|a| self.alternative_nullable(a)),
+            Expr::Many1(e) => self.nullable(e),
+            Expr::Many(_) | Expr::Optional(_) | Expr::And(_) | Expr::Not(_) => true,
+        }
+    }
+
+    fn alternative_nullable(&self, alternative : &Alternative) -> bool {
+        alternative.elements.iter().all(|e| self.nullable(&e.expr))
+    }
+
+    fn first(&self, expr : &Expr) -> BTreeSet<Terminal> {
+        match expr {
+            Expr::Rule(name) => self.first.get(name.as_str()).cloned().unwrap_or_default(),
+            Expr::Pattern(p) => BTreeSet::from([Terminal::pattern(p)]),
+            Expr::Any => BTreeSet::from([Terminal::Class(vec![(0, u32::MAX)])]),
+            Expr::Str(s) => s.chars().next().map(|c| BTreeSet::from([Terminal::Class(vec![(c as u32, c as u32)])])).unwrap_or_default(),
+            Expr::Pred(_) | Expr::Opaque(_) | Expr::And(_) | Expr::Not(_) => BTreeSet::new(),
+            Expr::Group(alternatives) => self.alternatives_first(alternatives),
+            Expr::Many(e) | Expr::Many1(e) | Expr::Optional(e) => self.first(e),
+        }
+    }
+
+    fn alternative_first(&self, alternative : &Alternative) -> BTreeSet<Terminal> {
+        let mut ret = BTreeSet::new();
+        for element in &alternative.elements {
+            ret.extend(self.first(&element.expr));
+            if !self.nullable(&element.expr) {
+                break;
+            }
+        }
+        ret
+    }
+
+    fn alternatives_first(&self, alternatives : &[Alternative]) -> BTreeSet<Terminal> {
+        alternatives.iter().flat_map(|a| self.alternative_first(a)).collect()
+    }
+
+    fn expand(&self, alternative : &'g Alternative, depth : usize) -> Vec<Expr> {
+        if let [element] = alternative.elements.as_slice() {
+            match &element.expr {
+                Expr::Rule(name) if depth < 32 => {
+                    if let Some(rule) = self.grammar.rule(name) {
+                        if let [inner] = rule.alternatives.as_slice() {
+                            return self.expand(inner, depth + 1);
+                        }
+                    }
+                },
+                Expr::Group(alternatives) if alternatives.len() == 1 => return self.expand(&alternatives[0], depth + 1),
+                _ => { },
+            }
+        }
+        alternative.elements.iter().flat_map(|e| match &e.expr {
+            Expr::Str(s) => s.chars().map(|c| Expr::Pattern(format!("{:?}", c))).collect(),
+            e => vec![e.clone()],
+        }).collect()
+    }
+
+    fn alternatives(&self, rule : &str, alternatives : &'g [Alternative], lints : &mut Vec<Lint>) {
+        let sequences : Vec<Vec<Expr>> = alternatives.iter().map(|a| self.expand(a, 0)).collect();
+        for j in 1..alternatives.len() {
+            for i in 0..j {
+                let nullable = self.alternative_nullable(&alternatives[i]) && !sequences[i].iter().any(|e| matches!(e, Expr::And(_) | Expr::Not(_) | Expr::Pred(_) | Expr::Opaque(_)));
+                if nullable || sequences[j].starts_with(&sequences[i]) {
+                    lints.push(Lint::ShadowedAlternative { rule: rule.to_string(), alternative: j + 1, by: i + 1, nullable });
+                    break;
+                }
+                let a = self.alternative_first(&alternatives[i]);
+                let b = self.alternative_first(&alternatives[j]);
+                if a.iter().any(|x| b.iter().any(|y| x.overlaps(y))) {
+                    lints.push(Lint::OverlappingAlternatives { rule: rule.to_string(), alternative: j + 1, with: i + 1 });
+                    break;
+                }
+            }
+        }
+        for alternative in alternatives {
+            for element in &alternative.elements {
+                self.expr(rule, &element.expr, lints);
+            }
+        }
+    }
+
+    fn expr(&self, rule : &str, expr : &'g Expr, lints : &mut Vec<Lint>) {
+        match expr {
+            Expr::Many(e) | Expr::Many1(e) => {
+                if self.nullable(e) {
+                    lints.push(Lint::NullableRepetition { rule: rule.to_string(), body: crate::diagram::text(e) });
+                }
+                self.expr(rule, e, lints);
+            },
+            Expr::Optional(e) | Expr::And(e) | Expr::Not(e) => self.expr(rule, e, lints),
+            Expr::Group(alternatives) => self.alternatives(rule, alternatives, lints),
+            _ => { },
+        }
+    }
+
+    fn left_calls(&self, alternatives : &'g [Alternative], ret : &mut BTreeSet<&'g str>) {
+        for alternative in alternatives {
+            for element in &alternative.elements {
+                self.left_calls_expr(&element.expr, ret);
+                if !self.nullable(&element.expr) {
+                    break;
+                }
+            }
+        }
+    }
+
+    fn left_calls_expr(&self, expr : &'g Expr, ret : &mut BTreeSet<&'g str>) {
+        match expr {
+            Expr::Rule(name) => { ret.insert(name); },
+            Expr::Group(alternatives) => self.left_calls(alternatives, ret),
+            Expr::Many(e) | Expr::Many1(e) | Expr::Optional(e) | Expr::And(e) | Expr::Not(e) => self.left_calls_expr(e, ret),
+            _ => { },
+        }
+    }
+
+    fn left_recursion(&self, lints : &mut Vec<Lint>) {
+        let calls : HashMap<&str, BTreeSet<&str>> = self.grammar.rules.iter().map(|r| {
+            let mut calls = BTreeSet::new();
+            self.left_calls(&r.alternatives, &mut calls);
+            (r.name.as_str(), calls)
+        }).collect();
+
+        fn path<'c>(calls : &HashMap<&'c str, BTreeSet<&'c str>>, from : &'c str, to : &str, seen : &mut BTreeSet<&'c str>) -> Option<Vec<&'c str>> {
+            for &next in calls.get(from).into_iter().flatten() {
+                if next == to {
+                    return Some(vec![next]);
+                }
+                if seen.insert(next) {
+                    if let Some(mut rest) = path(calls, next, to, seen) {
+                        rest.insert(0, next);
+                        return Some(rest);
+                    }
+                }
+            }
+            None
+        }
+
+        let mut reported = BTreeSet::new();
+        for rule in &self.grammar.rules {
+            if reported.contains(rule.name.as_str()) {
+                continue;
+            }
+            if let Some(rest) = path(&calls, &rule.name, &rule.name, &mut BTreeSet::new()) {
+                let mut cycle = vec![rule.name.as_str()];
+                cycle.extend(rest);
+                reported.extend(cycle.iter().copied());
+                lints.push(Lint::LeftRecursion { rule: rule.name.clone(), cycle: cycle.into_iter().map(str::to_string).collect() });
+            }
+        }
+    }
+}
+
+impl Grammar {
+    pub fn lint(&self) -> Vec<Lint> {
+        let analysis = Analysis::new(self);
+        let mut lints = vec![];
+        for rule in &self.rules {
+            analysis.alternatives(&rule.name, &rule.alternatives, &mut lints);
+        }
+        analysis.left_recursion(&mut lints);
+        lints
+    }
+
+    pub fn nullable(&self, rule : &str) -> Option<bool> {
+        self.rule(rule)?;
+        Some(Analysis::new(self).nullable[rule])
+    }
+
+    #[track_caller]
+    pub fn assert_lint_free(&self) {
+        let lints = self.lint();
+        if !lints.is_empty() {
+            let lines : Vec<String> = lints.iter().map(|l| format!("  {}", l)).collect();
+            panic!("grammar has {} lint warning(s):\n{}", lints.len(), lines.join("\n"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn lint_should_report_shadowed_alternatives() {
+        let g = grammar::Grammar::parse("kw = \"in\" / \"int\" / 'i' 'f' ;\nws = ' '* / 'x' ;\n").unwrap();
+
+        assert_eq!( g.lint(), vec![
+            Lint::ShadowedAlternative { rule: "kw".into(), alternative: 2, by: 1, nullable: false },
+            Lint::OverlappingAlternatives { rule: "kw".into(), alternative: 3, with: 1 },
+            Lint::ShadowedAlternative { rule: "ws".into(), alternative: 2, by: 1, nullable: true },
+        ] );
+        assert_eq!( g.lint()[0].to_string(), "rule `kw`: alternative 2 is unreachable because alternative 1 matches a prefix of it" );
+    }
+
+    #[test]
+    fn lint_should_report_nullable_repetitions_and_left_recursion() {
+        let g = grammar::Grammar::parse(concat!(
+            "list = ('a'?)* ;\n",
+            "expr = term '+' expr / term ;\n",
+            "term = factor? term '*' / 'x' ;\n",
+            "factor = ['0'..='9'] ;\n",
+        )).unwrap();
+
+        assert_eq!( g.lint(), vec![
+            Lint::NullableRepetition { rule: "list".into(), body: "('a'?)".into() },
+            Lint::OverlappingAlternatives { rule: "expr".into(), alternative: 2, with: 1 },
+            Lint::OverlappingAlternatives { rule: "term".into(), alternative: 2, with: 1 },
+            Lint::LeftRecursion { rule: "term".into(), cycle: vec!["term".into(), "term".into()] },
+        ] );
+        assert_eq!( g.nullable("list"), Some(true) );
+        assert_eq!( g.nullable("factor"), Some(false) );
+    }
+
+    grammar! {
+        fn described;
+        seq!(digit<'a> : char => char = d <= '0'..='9', { d });
+        seq!(pair<'a> : char => char = d <= '0'..='9', _e <= '0'..='9', { d });
+        seq!(letter<'a> : char => char = c <= 'a'..='z', { c });
+        alt!(shadowed<'a> : char => char = digit | pair);
+        alt!(fine<'a> : char => char = letter | digit);
+    }
+
+    #[test]
+    fn lint_should_check_macro_grammars() {
+        assert!( shadowed(&mut "12".char_indices()).is_ok() );
+        assert!( fine(&mut "a".char_indices()).is_ok() );
+
+        let g = described();
+
+        assert_eq!( g.lint(), vec![Lint::ShadowedAlternative { rule: "shadowed".into(), alternative: 2, by: 1, nullable: false }] );
+
+        let fine = grammar::Grammar { rules: g.rules.into_iter().filter(|r| r.name != "shadowed").collect(), input: String::new() };
+
+        fine.assert_lint_free();
+    }
+
+    #[test]
+    #[should_panic(expected = "rule `a` is left-recursive: a -> b -> a")]
+    fn assert_lint_free_should_panic_with_warnings() {
+        grammar::Grammar::parse("a = b 'x' ;\nb = a? 'y' / 'z' ;\n").unwrap().assert_lint_free();
+    }
+}
@@ -0,0 +1,462 @@
+
+use std::io::{BufRead, Read, Write};
+use std::process::ExitCode;
+
+use array_pattern::grammar::{Grammar, GrammarError, Interpreter, ItemType, TreeResult};
+use array_pattern::{peek_position, with_limits, Limits, MatchError, MatchInput, TraceEvent};
+
+const USAGE : &str = "usage: array_pattern [--start RULE] [--span] [--max-steps N] [--max-depth N] GRAMMAR [FILE...]\n       array_pattern --repl [GRAMMAR]\n       array_pattern --dot|--svg|--lint GRAMMAR";
+
+const HELP : &str = "\
+rule definitions, e.g. `digits : char = ['0'..='9']+ ;`, add or replace rules
+any other line is matched against the start rule
+:start RULE    choose the start rule (default: first rule)
+:input TYPE    set the item type (`char` or `u8`)
+:load FILE     replace the grammar with a grammar file
+:rules         list the rules
+:match TEXT    match TEXT even if it looks like a rule definition
+:trace         show the match steps for the last input
+:quit          leave";
+
+struct Options {
+    grammar : String,
+    start : Option<String>,
+    span : bool,
+    repl : bool,
+    export : Option<&'static str>,
+    limits : Limits,
+    files : Vec<String>,
+}
+
+fn count(option : &str, value : Option<String>) -> Result<usize, String> {
+    value.and_then(|v| v.parse().ok()).ok_or_else(|| format!("`{}` requires a number", option))
+}
+
+fn options(args : impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut ret = Options { grammar: String::new(), start: None, span: false, repl: false, export: None, limits: Limits::new(), files: vec![] };
+    let mut args = args.peekable();
+    let mut positional = vec![];
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--start" => ret.start = Some(args.next().ok_or("`--start` requires a rule name")?),
+            "--span" => ret.span = true,
+            "--max-steps" => ret.limits = ret.limits.steps(count(&arg, args.next())?),
+            "--max-depth" => ret.limits = ret.limits.depth(count(&arg, args.next())?),
+            "--repl" => ret.repl = true,
+            "--dot" => ret.export = Some("dot"),
+            "--svg" => ret.export = Some("svg"),
+            "--lint" => ret.export = Some("lint"),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            a if a.starts_with("--") => return Err(format!("unknown option `{}`", a)),
+            _ => positional.push(arg),
+        }
+    }
+    if positional.is_empty() {
+        if ret.repl {
+            return Ok(ret);
+        }
+        return Err(USAGE.to_string());
+    }
+    if ret.repl && positional.len() > 1 {
+        return Err(USAGE.to_string());
+    }
+    ret.grammar = positional.remove(0);
+    ret.files = positional;
+    Ok(ret)
+}
+
+fn location(src : &[u8], chars : bool, offset : usize) -> (usize, usize) {
+    let before = &src[..std::cmp::min(offset, src.len())];
+    let line = before.iter().filter(|&&b| b == b'\n').count() + 1;
+    let last = before.iter().rposition(|&b| b == b'\n').map(|k| k + 1).unwrap_or(0);
+    let column = if chars {
+        String::from_utf8_lossy(&before[last..]).chars().count() + 1
+    }
+    else {
+        before.len() - last + 1
+    };
+    (line, column)
+}
+
+fn render(name : &str, src : &[u8], chars : bool, start : &str, e : &MatchError) -> String {
+    let (offset, message) = match e {
+        MatchError::Error(i) => (*i, format!("expected `{}`", start)),
+        MatchError::Fatal(i) => (*i, "unexpected input".to_string()),
+        MatchError::ErrorEndOfFile => (src.len(), format!("expected `{}`, found end of input", start)),
+        MatchError::FatalEndOfFile => (src.len(), "unexpected end of input".to_string()),
+        MatchError::Incomplete(_) => (src.len(), "incomplete input".to_string()),
+        MatchError::Unseekable(offset) => (src.len(), format!("input cannot seek to offset {}", offset)),
+        MatchError::Io(e) => return format!("{}: error: {}", name, e),
+        MatchError::Checksum { expected, actual, start, .. } => (*start, format!("checksum mismatch: expected {:#x}, found {:#x}", expected, actual)),
+        MatchError::LimitExceeded { limit, position } => (position.unwrap_or(src.len()), limit.to_string()),
+    };
+    report(name, src, chars, offset, &message)
+}
+
+fn report(name : &str, src : &[u8], chars : bool, offset : usize, message : &str) -> String {
+    let (line, column) = location(src, chars, offset);
+    let found = match (offset < src.len(), chars) {
+        (false, _) => String::new(),
+        (true, true) => String::from_utf8_lossy(&src[offset..]).chars().next().map(|c| format!(", found {:?}", c)).unwrap_or_default(),
+        (true, false) => format!(", found {:#04x}", src[offset]),
+    };
+    format!("{}:{}:{}: error: {}{}", name, line, column, message, found)
+}
+
+fn interpret(interpreter : &Interpreter, start : &str, src : &[u8], trace : bool) -> Result<(TreeResult, Vec<TraceEvent>, Option<usize>), GrammarError> {
+    fn go<I>(interpreter : &Interpreter, start : &str, input : &mut I, trace : bool) -> Result<(TreeResult, Vec<TraceEvent>, Option<usize>), GrammarError>
+        where I : MatchInput, I::Item : Into<u32> {
+
+        let (result, steps) = if trace {
+            interpreter.trace(start, input)?
+        }
+        else {
+            (interpreter.run(start, input)?, vec![])
+        };
+        Ok((result, steps, peek_position(input)))
+    }
+    match interpreter.item_type() {
+        ItemType::Byte => go(interpreter, start, &mut src.iter().map(|&b| b as u32).enumerate(), trace),
+        ItemType::Char => match std::str::from_utf8(src) {
+            Ok(text) => go(interpreter, start, &mut text.char_indices().map(|(i, c)| (i, c as u32)), trace),
+            Err(e) => Err(GrammarError { line: 0, column: 0, message: format!("input is not valid UTF-8 at byte {}", e.valid_up_to()) }),
+        },
+    }
+}
+
+fn run(interpreter : &Interpreter, start : &str, span : bool, name : &str, src : &[u8]) -> Result<bool, GrammarError> {
+    let chars = interpreter.item_type() == ItemType::Char;
+    if let (true, Err(e)) = (chars, std::str::from_utf8(src)) {
+        eprintln!("{}", report(name, src, false, e.valid_up_to(), "input is not valid UTF-8"));
+        return Ok(false);
+    }
+    Ok(match interpret(interpreter, start, src, false)? {
+        (Ok(_), _, Some(rest)) => {
+            eprintln!("{}", report(name, src, chars, rest, "unexpected input after the match"));
+            false
+        },
+        (Ok(s), _, None) if span => {
+            println!("{}: {}..={}", name, s.start, s.end);
+            true
+        },
+        (Ok(s), _, None) => {
+            print!("{}", s.item);
+            true
+        },
+        (Err(e), _, _) => {
+            eprintln!("{}", render(name, src, chars, start, &e));
+            false
+        },
+    })
+}
+
+fn definition(line : &str) -> bool {
+    let line = line.trim_start();
+    if line.starts_with('@') {
+        return true;
+    }
+    if !line.starts_with(|c : char| c.is_ascii_alphabetic() || c == '_') {
+        return false;
+    }
+    let rest = line.trim_start_matches(|c : char| c.is_ascii_alphanumeric() || c == '_').trim_start();
+    (rest.starts_with(':') && !rest.starts_with("::")) || (rest.starts_with('=') && !rest.starts_with("==") && !rest.starts_with("=>"))
+}
+
+struct Repl {
+    grammar : Grammar,
+    start : Option<String>,
+    last : Option<String>,
+}
+
+impl Repl {
+    fn new(grammar : Grammar) -> Repl {
+        Repl { grammar, start: None, last: None }
+    }
+
+    fn interpreter(&self) -> Result<(Interpreter, String), String> {
+        let start = match &self.start {
+            Some(start) => start.clone(),
+            None => self.grammar.rules.first().map(|r| r.name.clone()).ok_or("no rules defined")?,
+        };
+        let interpreter = Interpreter::new(self.grammar.clone()).map_err(|e| format!("error: {}", e.message))?;
+        Ok((interpreter, start))
+    }
+
+    fn line(&mut self, line : &str) -> String {
+        let (command, rest) = match line.split_once(char::is_whitespace) {
+            Some((command, rest)) => (command, rest.trim()),
+            None => (line, ""),
+        };
+        match command {
+            ":help" => HELP.to_string(),
+            ":rules" => self.grammar.rules.iter().map(|r| format!("{} : {}", r.name, r.ty)).collect::<Vec<_>>().join("\n"),
+            ":start" if !rest.is_empty() => {
+                self.start = Some(rest.to_string());
+                format!("start rule is `{}`", rest)
+            },
+            ":input" if !rest.is_empty() => {
+                self.grammar.input = rest.to_string();
+                format!("item type is `{}`", rest)
+            },
+            ":load" if !rest.is_empty() => match std::fs::read_to_string(rest) {
+                Ok(src) => match Grammar::parse(&src) {
+                    Ok(grammar) => {
+                        self.grammar = grammar;
+                        format!("loaded {} rules", self.grammar.rules.len())
+                    },
+                    Err(e) => format!("{}:{}: error: {}", rest, e.line, e.message),
+                },
+                Err(e) => format!("{}: error: {}", rest, e),
+            },
+            ":match" => self.run(rest),
+            ":trace" => match &self.last {
+                Some(last) => match self.interpreter() {
+                    Ok((interpreter, start)) => match interpret(&interpreter, &start, last.as_bytes(), true) {
+                        Ok((_, steps, _)) => steps.iter().map(|s| s.to_string()).collect::<Vec<_>>().join("\n"),
+                        Err(e) => e.message,
+                    },
+                    Err(e) => e,
+                },
+                None => "nothing matched yet".to_string(),
+            },
+            c if c.starts_with(':') => format!("unknown command `{}`; try :help", line),
+            _ if definition(line) => {
+                let mut grammar = self.grammar.clone();
+                match grammar.define(line) {
+                    Ok(names) => {
+                        self.grammar = grammar;
+                        format!("defined {}", names.join(", "))
+                    },
+                    Err(e) => format!("<input>:{}:{}: error: {}", e.line, e.column, e.message),
+                }
+            },
+            _ => self.run(line),
+        }
+    }
+
+    fn run(&mut self, text : &str) -> String {
+        self.last = Some(text.to_string());
+        let (interpreter, start) = match self.interpreter() {
+            Ok(i) => i,
+            Err(e) => return e,
+        };
+        let chars = interpreter.item_type() == ItemType::Char;
+        let result = match interpret(&interpreter, &start, text.as_bytes(), false) {
+            Ok((result, _, _)) => result,
+            Err(e) => return e.message,
+        };
+        match result {
+            Ok(s) => {
+                let end = if chars { s.end + text[s.end..].chars().next().map(char::len_utf8).unwrap_or(0) } else { s.end + 1 };
+                let consumed = String::from_utf8_lossy(text.as_bytes().get(s.start..end).unwrap_or_default());
+                format!("matched {}..={} {:?}", s.start, s.end, consumed)
+            },
+            Err(e) => {
+                let rendered = render("<input>", text.as_bytes(), chars, &start, &e);
+                let column = location(text.as_bytes(), chars, match e {
+                    MatchError::Error(i) | MatchError::Fatal(i) => i,
+                    _ => text.len(),
+                }).1;
+                format!("{}\n  {}\n  {:>column$}", rendered, text, "^", column = column)
+            },
+        }
+    }
+}
+
+fn repl(grammar : Grammar) -> ExitCode {
+    let mut repl = Repl::new(grammar);
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        print!("> ");
+        let _ = std::io::stdout().flush();
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("error: {}", e);
+                return ExitCode::from(2);
+            },
+            None => return ExitCode::SUCCESS,
+        };
+        let line = line.trim();
+        if line == ":quit" || line == ":q" {
+            return ExitCode::SUCCESS;
+        }
+        if !line.is_empty() {
+            println!("{}", repl.line(line));
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let options = match options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            return ExitCode::from(2);
+        },
+    };
+
+    if options.repl {
+        let grammar = if options.grammar.is_empty() {
+            Grammar { input: "char".to_string(), rules: vec![] }
+        }
+        else {
+            match std::fs::read_to_string(&options.grammar).map_err(|e| e.to_string()).and_then(|src| Grammar::parse(&src).map_err(|e| format!("{}: {}", e.line, e.message))) {
+                Ok(grammar) => grammar,
+                Err(e) => {
+                    eprintln!("{}:{}", options.grammar, e);
+                    return ExitCode::from(2);
+                },
+            }
+        };
+        return repl(grammar);
+    }
+
+    if let Some(format) = options.export {
+        return match std::fs::read_to_string(&options.grammar) {
+            Ok(src) => match Grammar::parse(&src) {
+                Ok(grammar) if format == "lint" => {
+                    let lints = grammar.lint();
+                    for lint in &lints {
+                        println!("{}: warning: {}", options.grammar, lint);
+                    }
+                    if lints.is_empty() { ExitCode::SUCCESS } else { ExitCode::from(1) }
+                },
+                Ok(grammar) => {
+                    print!("{}", if format == "dot" { grammar.to_dot() } else { grammar.to_svg() });
+                    ExitCode::SUCCESS
+                },
+                Err(e) => {
+                    eprintln!("{}:{}: error: {}", options.grammar, e.line, e.message);
+                    ExitCode::from(2)
+                },
+            },
+            Err(e) => {
+                eprintln!("{}: error: {}", options.grammar, e);
+                ExitCode::from(2)
+            },
+        };
+    }
+
+    let interpreter = match std::fs::read_to_string(&options.grammar) {
+        Ok(src) => match Grammar::parse(&src).and_then(Interpreter::new) {
+            Ok(interpreter) => interpreter,
+            Err(e) => {
+                eprintln!("{}:{}: error: {}", options.grammar, e.line, e.message);
+                return ExitCode::from(2);
+            },
+        },
+        Err(e) => {
+            eprintln!("{}: error: {}", options.grammar, e);
+            return ExitCode::from(2);
+        },
+    };
+
+    let start = match &options.start {
+        Some(start) => start.clone(),
+        None => match interpreter.grammar().rules.first() {
+            Some(rule) => rule.name.clone(),
+            None => {
+                eprintln!("{}: error: grammar has no rules", options.grammar);
+                return ExitCode::from(2);
+            },
+        },
+    };
+
+    let files = if options.files.is_empty() { vec!["-".to_string()] } else { options.files.clone() };
+    let mut matched = true;
+    for file in &files {
+        let mut src = vec![];
+        let read = if file == "-" {
+            std::io::stdin().read_to_end(&mut src).map(|_| ())
+        }
+        else {
+            std::fs::File::open(file).and_then(|mut f| f.read_to_end(&mut src)).map(|_| ())
+        };
+        if let Err(e) = read {
+            eprintln!("{}: error: {}", file, e);
+            return ExitCode::from(2);
+        }
+        let name = if file == "-" { "<stdin>" } else { file };
+        match with_limits(options.limits, || run(&interpreter, &start, options.span, name, &src)) {
+            Ok(m) => matched &= m,
+            Err(e) => {
+                eprintln!("{}: error: {}", options.grammar, e);
+                return ExitCode::from(2);
+            },
+        }
+    }
+
+    if matched { ExitCode::SUCCESS } else { ExitCode::from(1) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use array_pattern::Limit;
+
+    #[test]
+    fn options_should_parse_flags_and_files() {
+        let o = options(["--start", "expr", "g.peg", "a.txt", "--span"].map(String::from).into_iter()).unwrap();
+
+        assert_eq!( o.grammar, "g.peg" );
+        assert_eq!( o.start.as_deref(), Some("expr") );
+        assert!( o.span );
+        assert_eq!( o.files, vec!["a.txt"] );
+
+        assert!( options(std::iter::empty()).is_err() );
+        assert!( options(["--bogus", "g.peg"].map(String::from).into_iter()).is_err() );
+
+        let o = options(["--max-steps", "100", "--max-depth", "8", "g.peg"].map(String::from).into_iter()).unwrap();
+
+        assert_eq!( o.limits, Limits::new().steps(100).depth(8) );
+        assert!( options(["--max-depth", "deep", "g.peg"].map(String::from).into_iter()).is_err() );
+    }
+
+    #[test]
+    fn render_should_report_line_and_column() {
+        let src = "ab\ncé!".as_bytes();
+
+        assert_eq!( render("f", src, true, "s", &MatchError::Fatal(6)), "f:2:3: error: unexpected input, found '!'" );
+        assert_eq!( render("f", src, false, "s", &MatchError::Error(1)), "f:1:2: error: expected `s`, found 0x62" );
+        assert_eq!( render("f", src, true, "s", &MatchError::FatalEndOfFile), "f:2:4: error: unexpected end of input" );
+        assert_eq!( render("f", src, true, "s", &MatchError::LimitExceeded { limit: Limit::Depth(3), position: Some(3) }), "f:2:1: error: depth limit of 3 exceeded, found 'c'" );
+    }
+
+    #[test]
+    fn run_should_reject_unconsumed_and_invalid_input() -> Result<(), GrammarError> {
+        let interpreter = Interpreter::new(Grammar::parse("digits = ['0'..='9']+ ;\n")?)?;
+
+        assert!( run(&interpreter, "digits", true, "f", b"123")? );
+        assert!( !run(&interpreter, "digits", true, "f", b"12x")? );
+        assert!( !run(&interpreter, "digits", true, "f", b"1\xff")? );
+        assert_eq!( report("f", b"1\xff", false, 1, "input is not valid UTF-8"), "f:1:2: error: input is not valid UTF-8, found 0xff" );
+
+        Ok(())
+    }
+
+    #[test]
+    fn repl_should_define_rules_and_match_input() {
+        let mut repl = Repl::new(Grammar { input: "char".to_string(), rules: vec![] });
+
+        assert_eq!( repl.line("list = digit (',' digit)* ;"), "defined list" );
+        assert_eq!( repl.line("12"), "error: undefined rule `digit`" );
+        assert_eq!( repl.line("digit = ['0'..='9'] ;"), "defined digit" );
+        assert_eq!( repl.line("item = digit"), "<input>:1:13: error: expected `;` at the end of the rule" );
+        assert_eq!( repl.line("item : u32 = { 0 ;"), "<input>:1:15: error: unterminated action block" );
+        assert_eq!( repl.line("1,2;"), "matched 0..=2 \"1,2\"" );
+        assert_eq!( repl.line("1,x"), "<input>:1:3: error: unexpected input, found 'x'\n  1,x\n    ^" );
+
+        assert!( repl.line(":trace").starts_with("list at 0\n  digit at 0\n  digit matched 0..=0\n") );
+
+        assert_eq!( repl.line(":start digit"), "start rule is `digit`" );
+        assert_eq!( repl.line("7"), "matched 0..=0 \"7\"" );
+        assert_eq!( repl.line(":rules"), "list : ()\ndigit : ()" );
+        assert_eq!( repl.line(":start nope"), "start rule is `nope`" );
+        assert_eq!( repl.line("7"), "undefined start rule `nope`" );
+        assert_eq!( repl.line(":start digit"), "start rule is `digit`" );
+        assert_eq!( repl.line(":input u16"), "item type is `u16`" );
+        assert_eq!( repl.line("7"), "error: unsupported item type `u16`; expected `char` or `u8`" );
+    }
+}
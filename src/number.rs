@@ -0,0 +1,462 @@
+
+use std::borrow::Borrow;
+
+use crate::{MatchError, MatchInput, Success};
+
+pub fn bytes<const N : usize>(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<[u8; N]>, MatchError> {
+    crate::instrument(input, "bytes", read_bytes::<N>)
+}
+
+fn read_bytes<const N : usize>(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<[u8; N]>, MatchError> {
+    let rp = input.checkpoint();
+    let mut ret = [0u8; N];
+    let mut start : usize = 0;
+    let mut end : usize = 0;
+    for (k, slot) in ret.iter_mut().enumerate() {
+        match input.next_item() {
+            Some((i, b)) => {
+                if k == 0 {
+                    start = i;
+                }
+                end = i;
+                *slot = *b.borrow();
+            },
+            None => {
+                let e = if input.is_partial() {
+                    MatchError::Incomplete(Some(N - k))
+                }
+                else {
+                    input.end_of_input(k != 0)
+                };
+                crate::reset(input, rp);
+                return Err(e);
+            },
+        }
+    }
+    Ok(Success { item: ret, start, end })
+}
+
+macro_rules! numbers {
+    ($($name:ident : $t:ty = $conv:ident;)*) => {
+        $(
+            pub fn $name(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<$t>, MatchError> {
+                crate::instrument(input, stringify!($name), |input| {
+                    let s = read_bytes::<{ std::mem::size_of::<$t>() }>(input)?;
+                    Ok(Success { item: <$t>::$conv(s.item), start: s.start, end: s.end })
+                })
+            }
+        )*
+    };
+}
+
+numbers! {
+    be_u16 : u16 = from_be_bytes;
+    le_u16 : u16 = from_le_bytes;
+    be_u32 : u32 = from_be_bytes;
+    le_u32 : u32 = from_le_bytes;
+    be_u64 : u64 = from_be_bytes;
+    le_u64 : u64 = from_le_bytes;
+    be_u128 : u128 = from_be_bytes;
+    le_u128 : u128 = from_le_bytes;
+    be_i16 : i16 = from_be_bytes;
+    le_i16 : i16 = from_le_bytes;
+    be_i32 : i32 = from_be_bytes;
+    le_i32 : i32 = from_le_bytes;
+    be_i64 : i64 = from_be_bytes;
+    le_i64 : i64 = from_le_bytes;
+    be_i128 : i128 = from_be_bytes;
+    le_i128 : i128 = from_le_bytes;
+    be_f32 : f32 = from_be_bytes;
+    le_f32 : f32 = from_le_bytes;
+    be_f64 : f64 = from_be_bytes;
+    le_f64 : f64 = from_le_bytes;
+}
+
+fn leb128(input : &mut impl MatchInput<Item = impl Borrow<u8>>, width : u32, signed : bool) -> Result<Success<u64>, MatchError> {
+    let rp = input.checkpoint();
+    let mut value : u64 = 0;
+    let mut shift : u32 = 0;
+    let mut start : usize = 0;
+    let mut prev : Option<u8> = None;
+    loop {
+        let (i, byte) = match input.next_item() {
+            Some((i, b)) => (i, *b.borrow()),
+            None => {
+                let e = input.end_of_input(prev.is_some());
+                crate::reset(input, rp);
+                return Err(e);
+            },
+        };
+        if prev.is_none() {
+            start = i;
+        }
+
+        let payload = (byte & 0x7F) as u64;
+        let overflow = if byte & 0x80 != 0 && shift + 7 >= width {
+            true
+        }
+        else if width - shift < 7 {
+            let remaining = width - shift;
+            let extra = payload >> remaining;
+            let negative = payload & (1 << (remaining - 1)) != 0;
+            if signed && negative {
+                extra != (0x7F >> remaining)
+            }
+            else {
+                extra != 0
+            }
+        }
+        else {
+            false
+        };
+
+        if overflow {
+            crate::reset(input, rp);
+            return Err(MatchError::Fatal(i));
+        }
+
+        value |= payload << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            let overlong = match prev {
+                Some(p) if signed => (byte == 0x00 && p & 0x40 == 0) || (byte == 0x7F && p & 0x40 != 0),
+                Some(_) => byte == 0x00,
+                None => false,
+            };
+            if overlong {
+                crate::reset(input, rp);
+                return Err(MatchError::Fatal(i));
+            }
+            if signed && shift < 64 && byte & 0x40 != 0 {
+                value |= !0u64 << shift;
+            }
+            return Ok(Success { item: value, start, end: i });
+        }
+        prev = Some(byte);
+    }
+}
+
+pub fn uleb128_u32(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<u32>, MatchError> {
+    crate::instrument(input, "uleb128_u32", |input| {
+        let s = leb128(input, 32, false)?;
+        Ok(Success { item: s.item as u32, start: s.start, end: s.end })
+    })
+}
+
+pub fn uleb128_u64(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<u64>, MatchError> {
+    crate::instrument(input, "uleb128_u64", |input| leb128(input, 64, false))
+}
+
+pub fn sleb128_i32(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<i32>, MatchError> {
+    crate::instrument(input, "sleb128_i32", |input| {
+        let s = leb128(input, 32, true)?;
+        Ok(Success { item: s.item as i32, start: s.start, end: s.end })
+    })
+}
+
+pub fn sleb128_i64(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<i64>, MatchError> {
+    crate::instrument(input, "sleb128_i64", |input| {
+        let s = leb128(input, 64, true)?;
+        Ok(Success { item: s.item as i64, start: s.start, end: s.end })
+    })
+}
+
+pub fn vlq_u32(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<u32>, MatchError> {
+    crate::instrument(input, "vlq_u32", |input| {
+        let rp = input.checkpoint();
+        let mut value : u32 = 0;
+        let mut start : usize = 0;
+        let mut count = 0;
+        loop {
+            let (i, byte) = match input.next_item() {
+                Some((i, b)) => (i, *b.borrow()),
+                None => {
+                    let e = input.end_of_input(count != 0);
+                    crate::reset(input, rp);
+                    return Err(e);
+                },
+            };
+            if count == 0 {
+                start = i;
+            }
+            if (count == 0 && byte == 0x80) || count == 4 {
+                crate::reset(input, rp);
+                return Err(MatchError::Fatal(i));
+            }
+            value = (value << 7) | (byte & 0x7F) as u32;
+            count += 1;
+            if byte & 0x80 == 0 {
+                return Ok(Success { item: value, start, end: i });
+            }
+        }
+    })
+}
+
+#[macro_export]
+macro_rules! number {
+    ($matcher_name:ident<$life:lifetime> : $out_t:ty = $reader:ident == $value:expr) => {
+        #[allow(clippy::extra_unused_lifetimes, clippy::float_cmp)]
+        fn $matcher_name<$life>(input : &mut impl MatchInput<Item = impl std::borrow::Borrow<u8>>) -> Result<Success<$out_t>, MatchError> {
+            $crate::instrument(input, stringify!($matcher_name), |input| {
+                let rp = input.checkpoint();
+                let s = $crate::number::$reader(input)?;
+                if s.item == $value {
+                    Ok(s)
+                }
+                else {
+                    $crate::reset(input, rp);
+                    Err(MatchError::Error(s.start))
+                }
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[test]
+    fn unsigned_matchers_should_read_both_byte_orders() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![0x01, 0x02, 0x01, 0x02, 0x01, 0x02, 0x03, 0x04, 0x01, 0x02, 0x03, 0x04];
+        let mut i = v.into_iter().enumerate();
+
+        assert_eq!( be_u16(&mut i)?.item, 0x0102 );
+        assert_eq!( le_u16(&mut i)?.item, 0x0201 );
+        assert_eq!( be_u32(&mut i)?.item, 0x01020304 );
+
+        let o = le_u32(&mut i)?;
+
+        assert_eq!( o.item, 0x04030201 );
+        assert_eq!( o.start, 8 );
+        assert_eq!( o.end, 11 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn wide_matchers_should_read_both_byte_orders() -> Result<(), MatchError> {
+        let v : Vec<u8> = (1..=16).collect();
+        let mut i = SliceInput::new(&v);
+
+        assert_eq!( be_u64(&mut i)?.item, 0x0102030405060708 );
+        assert_eq!( le_u64(&mut i)?.item, 0x100F0E0D0C0B0A09 );
+
+        let mut i = SliceInput::new(&v);
+
+        assert_eq!( be_u128(&mut i)?.item, 0x0102030405060708090A0B0C0D0E0F10 );
+
+        let mut i = SliceInput::new(&v);
+
+        assert_eq!( le_u128(&mut i)?.item, 0x100F0E0D0C0B0A090807060504030201 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn signed_and_float_matchers_should_read_values() -> Result<(), MatchError> {
+        let mut v : Vec<u8> = vec![0xFF, 0xFE, 0xFE, 0xFF, 0xFF, 0xFF];
+        v.extend(1.5f32.to_be_bytes());
+        v.extend((-2.25f64).to_le_bytes());
+        let mut i = v.into_iter().enumerate();
+
+        assert_eq!( be_i16(&mut i)?.item, -2 );
+        assert_eq!( le_i32(&mut i)?.item, -2 );
+        assert_eq!( be_f32(&mut i)?.item, 1.5 );
+        assert_eq!( le_f64(&mut i)?.item, -2.25 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn numbers_should_work_inside_seq() -> Result<(), MatchError> {
+        seq!(zero_or_more ~ main<'a> : u8 => u16 = x <= be_u16, { x });
+
+        let v : Vec<u8> = vec![0x01, 0x02, 0x03, 0x04];
+        let mut i = v.into_iter().enumerate();
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item, vec![0x0102, 0x0304] );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 3 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn numbers_should_indicate_end_of_file_and_reset() {
+        seq!(main<'a> : u8 => () = _a <= 0xAA, _b <= be_u32, { () });
+
+        let v : Vec<u8> = vec![0xAA, 0x01, 0x02];
+        let mut i = v.into_iter().enumerate();
+
+        let o = be_u32(&mut i);
+
+        assert!( matches!( o, Err(MatchError::FatalEndOfFile) ) );
+        assert_eq!( i.clone().count(), 3 );
+
+        let o = main(&mut i);
+
+        assert!( matches!( o, Err(MatchError::FatalEndOfFile) ) );
+    }
+
+    #[test]
+    fn numbers_should_indicate_incomplete_with_missing_count() {
+        let v : Vec<u8> = vec![0x01];
+        let mut i = Partial::new(SliceInput::new(&v));
+
+        let o = be_u64(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Incomplete(Some(7))) ) );
+    }
+
+    #[test]
+    fn number_should_match_exact_value() -> Result<(), MatchError> {
+        number!(magic<'a> : u32 = be_u32 == 0xCAFEBABE);
+        seq!(class<'a> : u8 => u16 = _m <= magic, minor <= be_u16, { minor });
+
+        let v : Vec<u8> = vec![0xCA, 0xFE, 0xBA, 0xBE, 0x00, 0x03];
+        let mut i = v.into_iter().enumerate();
+
+        let o = class(&mut i)?;
+
+        assert_eq!( o.item, 3 );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 5 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn number_should_fail_at_first_byte_of_wrong_value() {
+        number!(magic<'a> : u32 = be_u32 == 0xCAFEBABE);
+        seq!(main<'a> : u8 => () = _a <= 0x00, _m <= magic, { () });
+
+        let v : Vec<u8> = vec![0x00, 0xCA, 0xFE, 0xBA, 0xBF];
+        let mut i = v.into_iter().enumerate();
+
+        let o = main(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(1)) ) );
+    }
+
+    #[test]
+    fn uleb128_should_read_values() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![0xE5, 0x8E, 0x26, 0xFF, 0xFF, 0xFF, 0xFF, 0x0F, 0x00];
+        let mut i = SliceInput::new(&v);
+
+        let o = uleb128_u32(&mut i)?;
+
+        assert_eq!( o.item, 624485 );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 2 );
+        assert_eq!( uleb128_u32(&mut i)?.item, u32::MAX );
+        assert_eq!( uleb128_u64(&mut i)?.item, 0 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn uleb128_should_reject_overlong_encoding() {
+        let v : Vec<u8> = vec![0x85, 0x80, 0x00];
+        let mut i = SliceInput::new(&v);
+
+        let o = uleb128_u64(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(2)) ) );
+        assert_eq!( i.position(), 0 );
+    }
+
+    #[test]
+    fn uleb128_should_reject_overflow() {
+        let v : Vec<u8> = vec![0xFF, 0xFF, 0xFF, 0xFF, 0x1F];
+        let mut i = SliceInput::new(&v);
+
+        let o = uleb128_u32(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(4)) ) );
+
+        let v : Vec<u8> = vec![0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x01];
+        let mut i = SliceInput::new(&v);
+
+        let o = uleb128_u64(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Fatal(9)) ) );
+    }
+
+    #[test]
+    fn sleb128_should_read_values() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![0xC0, 0xBB, 0x78, 0x80, 0x80, 0x80, 0x80, 0x78, 0x3F, 0x40];
+        let mut i = SliceInput::new(&v);
+
+        assert_eq!( sleb128_i64(&mut i)?.item, -123456 );
+        assert_eq!( sleb128_i32(&mut i)?.item, i32::MIN );
+        assert_eq!( sleb128_i32(&mut i)?.item, 63 );
+        assert_eq!( sleb128_i32(&mut i)?.item, -64 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn sleb128_should_reject_overlong_and_overflow() {
+        let v : Vec<u8> = vec![0xFF, 0x7F];
+        let mut i = SliceInput::new(&v);
+
+        assert!( matches!( sleb128_i32(&mut i), Err(MatchError::Fatal(1)) ) );
+
+        let v : Vec<u8> = vec![0x80, 0x80, 0x80, 0x80, 0x70];
+        let mut i = SliceInput::new(&v);
+
+        assert!( matches!( sleb128_i32(&mut i), Err(MatchError::Fatal(4)) ) );
+    }
+
+    #[test]
+    fn vlq_should_read_values() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![0x00, 0x7F, 0x81, 0x00, 0xFF, 0xFF, 0xFF, 0x7F];
+        let mut i = SliceInput::new(&v);
+
+        assert_eq!( vlq_u32(&mut i)?.item, 0 );
+        assert_eq!( vlq_u32(&mut i)?.item, 0x7F );
+        assert_eq!( vlq_u32(&mut i)?.item, 0x80 );
+        assert_eq!( vlq_u32(&mut i)?.item, 0x0FFFFFFF );
+
+        Ok(())
+    }
+
+    #[test]
+    fn vlq_should_reject_overlong_and_overflow() {
+        let v : Vec<u8> = vec![0x80, 0x01];
+        let mut i = SliceInput::new(&v);
+
+        assert!( matches!( vlq_u32(&mut i), Err(MatchError::Fatal(0)) ) );
+
+        let v : Vec<u8> = vec![0x81, 0x80, 0x80, 0x80, 0x00];
+        let mut i = SliceInput::new(&v);
+
+        assert!( matches!( vlq_u32(&mut i), Err(MatchError::Fatal(4)) ) );
+    }
+
+    #[test]
+    fn varints_should_work_inside_seq() -> Result<(), MatchError> {
+        seq!(field<'a> : &'a u8 => (u64, i64) = tag <= uleb128_u64, value <= sleb128_i64, { (tag, value) });
+        seq!(main<'a> : &'a u8 => (u64, i64) = _a <= 0xAA, f <= field, { f });
+
+        let v : Vec<u8> = vec![0xAA, 0x96, 0x01, 0x7E];
+        let mut i = SliceInput::new(&v);
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item, (150, -2) );
+        assert_eq!( o.end, 3 );
+
+        let v : Vec<u8> = vec![0xAA, 0x96];
+        let mut i = SliceInput::new(&v);
+
+        assert!( matches!( main(&mut i), Err(MatchError::FatalEndOfFile) ) );
+
+        Ok(())
+    }
+}
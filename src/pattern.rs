@@ -0,0 +1,245 @@
+
+use std::borrow::Borrow;
+
+use crate::number::*;
+use crate::{MatchError, MatchInput, Success};
+
+pub trait Pattern<T> : Sized {
+    fn pattern(input : &mut impl MatchInput<Item = T>) -> Result<Success<Self>, MatchError>;
+}
+
+impl<T : Borrow<u8>> Pattern<T> for u8 {
+    fn pattern(input : &mut impl MatchInput<Item = T>) -> Result<Success<u8>, MatchError> {
+        let s = bytes::<1>(input)?;
+        Ok(Success { item: s.item[0], start: s.start, end: s.end })
+    }
+}
+
+impl<T : Borrow<u8>> Pattern<T> for i8 {
+    fn pattern(input : &mut impl MatchInput<Item = T>) -> Result<Success<i8>, MatchError> {
+        let s = bytes::<1>(input)?;
+        Ok(Success { item: s.item[0] as i8, start: s.start, end: s.end })
+    }
+}
+
+impl<T : Borrow<u8>, const N : usize> Pattern<T> for [u8; N] {
+    fn pattern(input : &mut impl MatchInput<Item = T>) -> Result<Success<[u8; N]>, MatchError> {
+        bytes::<N>(input)
+    }
+}
+
+macro_rules! patterns {
+    ($($t:ty = $reader:ident;)*) => {
+        $(
+            impl<T : Borrow<u8>> Pattern<T> for $t {
+                fn pattern(input : &mut impl MatchInput<Item = T>) -> Result<Success<$t>, MatchError> {
+                    $reader(input)
+                }
+            }
+        )*
+    };
+}
+
+patterns! {
+    u16 = be_u16;
+    u32 = be_u32;
+    u64 = be_u64;
+    u128 = be_u128;
+    i16 = be_i16;
+    i32 = be_i32;
+    i64 = be_i64;
+    i128 = be_i128;
+    f32 = be_f32;
+    f64 = be_f64;
+}
+
+#[doc(hidden)]
+pub fn derive_error<I : MatchInput>(input : &mut I, rp : I::Checkpoint, fatal : bool, e : MatchError) -> MatchError {
+    match e {
+        MatchError::Error(i) if fatal => MatchError::Fatal(i),
+        MatchError::ErrorEndOfFile if fatal => MatchError::FatalEndOfFile,
+        MatchError::Incomplete(n) => {
+            crate::reset(input, rp);
+            MatchError::Incomplete(n)
+        },
+        e => e,
+    }
+}
+
+#[doc(hidden)]
+pub fn derive_repeat<I, V>(input : &mut I, n : usize, matcher : impl Fn(&mut I) -> Result<Success<V>, MatchError>) -> Result<Success<Vec<V>>, MatchError>
+    where I : MatchInput {
+
+    let rp = input.checkpoint();
+    let mut ret = Vec::with_capacity(n);
+    let mut start : usize = 0;
+    let mut end : usize = 0;
+    for k in 0..n {
+        match matcher(input) {
+            Ok(s) => {
+                if k == 0 {
+                    start = s.start;
+                }
+                end = std::cmp::max(end, s.end);
+                ret.push(s.item);
+            },
+            Err(e) => return Err(derive_error(input, rp, k != 0, e)),
+        }
+    }
+    Ok(Success { item: ret, start, end })
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use std::borrow::Borrow;
+
+    use crate::*;
+    use crate::number::*;
+
+    #[derive(Pattern, Debug, PartialEq)]
+    #[pattern(bytes)]
+    struct Header {
+        #[pattern(literal = [0x89, b'P', b'N', b'G'])]
+        magic : [u8; 4],
+        version : u16,
+        #[pattern(with = le_u32)]
+        length : u32,
+    }
+
+    #[derive(Pattern, Debug, PartialEq)]
+    struct Record {
+        flags : u8,
+        count : u8,
+        #[pattern(count = count)]
+        values : Vec<u16>,
+        #[pattern(cond = flags & 0x01 != 0)]
+        checksum : Option<u8>,
+    }
+
+    #[derive(Pattern, Debug, PartialEq)]
+    struct Pair(u8, i8);
+
+    #[derive(Pattern, Debug, PartialEq)]
+    #[pattern(tag = u8)]
+    enum Message {
+        #[pattern(tag = 0x01)]
+        Ping,
+        #[pattern(tag = 0x02)]
+        Data(Pair),
+        #[pattern(tag = 0x03)]
+        Record { record : Record },
+    }
+
+    fn label<'a>(input : &mut impl MatchInput<Item = impl Borrow<u8>>) -> Result<Success<&'a str>, MatchError> {
+        let s = u8::pattern(input)?;
+        Ok(Success { item: if s.item == 0 { "zero" } else { "other" }, start: s.start, end: s.end })
+    }
+
+    #[derive(Pattern, Debug, PartialEq)]
+    struct Labelled<'a> {
+        #[pattern(with = label)]
+        label : &'a str,
+        #[pattern(with = be_u16)]
+        value : u16,
+    }
+
+    #[derive(Pattern, Debug, PartialEq)]
+    enum Either {
+        Zero(#[pattern(literal = 0)] u8),
+        Any(u16),
+    }
+
+    #[test]
+    fn derive_should_match_struct_fields_in_order() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![0x89, b'P', b'N', b'G', 0x00, 0x02, 0x10, 0x00, 0x00, 0x00];
+        let mut i = v.into_iter().enumerate();
+
+        let o = Header::pattern(&mut i)?;
+
+        assert_eq!( o.item, Header { magic: [0x89, b'P', b'N', b'G'], version: 2, length: 16 } );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 9 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn derive_should_report_literal_mismatch() {
+        let v : Vec<u8> = vec![0x89, b'J', b'P', b'G', 0x00, 0x02, 0x10, 0x00, 0x00, 0x00];
+        let mut i = SliceInput::new(&v);
+
+        let o = Header::pattern(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Error(0)) ) );
+        assert_eq!( i.position(), 0 );
+    }
+
+    #[test]
+    fn derive_should_apply_count_and_condition() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![0x01, 0x02, 0x00, 0x0A, 0x00, 0x0B, 0xFF, 0x00, 0x00, 0x00];
+        let mut i = SliceInput::new(&v);
+
+        let o = Record::pattern(&mut i)?;
+
+        assert_eq!( o.item, Record { flags: 1, count: 2, values: vec![10, 11], checksum: Some(0xFF) } );
+
+        let o = Record::pattern(&mut i)?;
+
+        assert_eq!( o.item, Record { flags: 0, count: 0, values: vec![], checksum: None } );
+        assert_eq!( i.position(), 9 );
+
+        let o = Record::pattern(&mut i);
+
+        assert!( matches!( o, Err(MatchError::FatalEndOfFile) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn derive_should_try_enum_variants_in_order() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![0x02, 0x07, 0xFF, 0x01, 0x03, 0x00, 0x00, 0x04];
+        let mut i = SliceInput::new(&v);
+
+        assert_eq!( Message::pattern(&mut i)?.item, Message::Data(Pair(7, -1)) );
+        assert_eq!( Message::pattern(&mut i)?.item, Message::Ping );
+        assert_eq!( Message::pattern(&mut i)?.item, Message::Record { record: Record { flags: 0, count: 0, values: vec![], checksum: None } } );
+        assert!( matches!( Message::pattern(&mut i), Err(MatchError::Error(7)) ) );
+
+        let v : Vec<u8> = vec![0x00, 0x01, 0x02];
+        let mut i = SliceInput::new(&v);
+
+        assert_eq!( Either::pattern(&mut i)?.item, Either::Zero(0) );
+        assert_eq!( Either::pattern(&mut i)?.item, Either::Any(0x0102) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn derive_should_handle_lifetimes_and_with_fields() -> Result<(), MatchError> {
+        let v : Vec<u8> = vec![0x00, 0x01, 0x02];
+        let mut i = SliceInput::new(&v);
+
+        let o = Labelled::pattern(&mut i)?;
+
+        assert_eq!( o.item, Labelled { label: "zero", value: 0x0102 } );
+        assert_eq!( o.end, 2 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn derived_patterns_should_work_inside_seq() -> Result<(), MatchError> {
+        group!(pair<'a> : u8 => Pair = |input| { Pair::pattern(input) });
+        seq!(main<'a> : u8 => (Pair, u8) = p <= pair, _s <= 0xFF, b <= _, { (p, b) });
+
+        let v : Vec<u8> = vec![0x01, 0x02, 0xFF, 0x03];
+        let mut i = v.into_iter().enumerate();
+
+        let o = main(&mut i)?;
+
+        assert_eq!( o.item, (Pair(1, 2), 3) );
+        assert_eq!( o.end, 3 );
+
+        Ok(())
+    }
+}
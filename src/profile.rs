@@ -0,0 +1,245 @@
+
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use crate::{MatchError, MatchInput, Success};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleProfile {
+    pub name : Cow<'static, str>,
+    pub calls : u64,
+    pub successes : u64,
+    pub errors : u64,
+    pub fatals : u64,
+    pub rescanned : u64,
+    pub time : Duration,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Profile {
+    pub rules : Vec<RuleProfile>,
+}
+
+impl Profile {
+    pub fn rule(&self, name : &str) -> Option<&RuleProfile> {
+        self.rules.iter().find(|r| r.name == name)
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut ret = "rule,calls,successes,errors,fatals,rescanned,time_us\n".to_string();
+        for r in &self.rules {
+            ret += &format!("{},{},{},{},{},{},{}\n", r.name, r.calls, r.successes, r.errors, r.fatals, r.rescanned, r.time.as_micros());
+        }
+        ret
+    }
+
+    pub fn to_json(&self) -> String {
+        let rules : Vec<String> = self.rules.iter().map(|r| format!(
+            "{{\"rule\":\"{}\",\"calls\":{},\"successes\":{},\"errors\":{},\"fatals\":{},\"rescanned\":{},\"time_us\":{}}}",
+            r.name.replace('\\', "\\\\").replace('"', "\\\""), r.calls, r.successes, r.errors, r.fatals, r.rescanned, r.time.as_micros())).collect();
+        format!("[{}]", rules.join(","))
+    }
+}
+
+impl fmt::Display for Profile {
+    fn fmt(&self, f : &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.rules.iter().map(|r| r.name.len()).max().unwrap_or(0).max(4);
+        writeln!(f, "{:width$} {:>10} {:>10} {:>10} {:>10} {:>10} {:>12}", "rule", "calls", "successes", "errors", "fatals", "rescanned", "time", width = width)?;
+        for r in &self.rules {
+            writeln!(f, "{:width$} {:>10} {:>10} {:>10} {:>10} {:>10} {:>12?}", r.name, r.calls, r.successes, r.errors, r.fatals, r.rescanned, r.time, width = width)?;
+        }
+        Ok(())
+    }
+}
+
+struct Collector {
+    rules : HashMap<Cow<'static, str>, RuleProfile>,
+    stack : Vec<(Cow<'static, str>, Instant)>,
+}
+
+thread_local! {
+    static COLLECTOR : RefCell<Option<Collector>> = const { RefCell::new(None) };
+}
+
+struct Restore {
+    previous : Option<Option<Collector>>,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            COLLECTOR.with(|c| c.replace(previous));
+        }
+    }
+}
+
+pub fn profile<R>(f : impl FnOnce() -> R) -> (R, Profile) {
+    let previous = COLLECTOR.with(|c| c.replace(Some(Collector { rules: HashMap::new(), stack: vec![] })));
+    let mut restore = Restore { previous: Some(previous) };
+    let ret = f();
+    let collector = COLLECTOR.with(|c| c.replace(restore.previous.take().unwrap())).unwrap();
+    let mut rules : Vec<RuleProfile> = collector.rules.into_values().collect();
+    rules.sort_by(|a, b| b.time.cmp(&a.time).then(a.name.cmp(&b.name)));
+    (ret, Profile { rules })
+}
+
+fn active() -> bool {
+    COLLECTOR.with(|c| c.borrow().is_some())
+}
+
+pub(crate) fn enter(matcher : impl Into<Cow<'static, str>>) {
+    COLLECTOR.with(|c| {
+        if let Some(c) = c.borrow_mut().as_mut() {
+            let matcher = matcher.into();
+            let rule = c.rules.entry(matcher.clone()).or_insert_with(|| RuleProfile { name: matcher.clone(), ..RuleProfile::default() });
+            rule.calls += 1;
+            c.stack.push((matcher, Instant::now()));
+        }
+    });
+}
+
+pub(crate) fn exit<T>(result : &Result<Success<T>, MatchError>) {
+    COLLECTOR.with(|c| {
+        if let Some(c) = c.borrow_mut().as_mut() {
+            let (matcher, started) = match c.stack.pop() {
+                Some(frame) => frame,
+                None => return,
+            };
+            let recursive = c.stack.iter().any(|(m, _)| *m == matcher);
+            let rule = c.rules.get_mut(&matcher).unwrap();
+            match result {
+                Ok(_) => rule.successes += 1,
+                Err(MatchError::Error(_)) | Err(MatchError::ErrorEndOfFile) => rule.errors += 1,
+                Err(_) => rule.fatals += 1,
+            }
+            if !recursive {
+                rule.time += started.elapsed();
+            }
+        }
+    });
+}
+
+pub(crate) fn before_reset<I : MatchInput>(input : &mut I) -> Option<Option<usize>> {
+    if !active() {
+        return None;
+    }
+    Some(crate::peek_position(input))
+}
+
+pub(crate) fn after_reset<I : MatchInput>(input : &mut I, before : Option<Option<usize>>) {
+    let before = match before {
+        Some(before) => before,
+        None => return,
+    };
+    let rp = input.checkpoint();
+    let mut rescanned = 0;
+    while let Some((i, _)) = input.next_item() {
+        if Some(i) == before {
+            break;
+        }
+        rescanned += 1;
+    }
+    input.restore(rp);
+    COLLECTOR.with(|c| {
+        if let Some(c) = c.borrow_mut().as_mut() {
+            if let Some((matcher, _)) = c.stack.last() {
+                c.rules.get_mut(matcher).unwrap().rescanned += rescanned;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    seq!(zero_or_more ~ digits<'a> : char => char = d <= '0'..='9', { d });
+    seq!(plus<'a> : char => char = _p <= '+', d <= '0'..='9', { d });
+    seq!(minus<'a> : char => char = _m <= '-', d <= '0'..='9', { d });
+    seq!(digit<'a> : char => char = d <= '0'..='9', { d });
+    alt!(op<'a> : char => char = plus | minus | digit);
+    seq!(nested<'a> : char => u32 = _o <= '(', n <= inner, _c <= ')', { n + 1 });
+    seq!(leaf<'a> : char => u32 = _x <= 'x', { 0 });
+    alt!(inner<'a> : char => u32 = nested | leaf);
+
+    #[test]
+    fn profile_should_count_calls_and_outcomes() {
+        let (o, report) = profile(|| {
+            ["-1", "5", "a", "+a"].iter().map(|s| op(&mut s.char_indices()).is_ok()).collect::<Vec<_>>()
+        });
+
+        assert_eq!( o, vec![true, true, false, false] );
+        assert_eq!( report.rule("op").map(|r| (r.calls, r.successes, r.errors, r.fatals)), Some((4, 2, 1, 1)) );
+        assert_eq!( report.rule("plus").map(|r| (r.calls, r.successes, r.errors, r.fatals)), Some((4, 0, 3, 1)) );
+        assert_eq!( report.rule("minus").map(|r| (r.calls, r.successes, r.errors)), Some((3, 1, 2)) );
+        assert_eq!( report.rule("digit").map(|r| (r.calls, r.successes, r.errors)), Some((2, 1, 1)) );
+    }
+
+    #[test]
+    fn profile_should_count_rescanned_items() {
+        let (_, report) = profile(|| {
+            let mut i = "123a".char_indices();
+            digits(&mut i)
+        });
+
+        assert_eq!( report.rule("digits").map(|r| (r.calls, r.successes)), Some((1, 1)) );
+        assert_eq!( report.rule("digits").unwrap().rescanned, 1 );
+
+        let (_, report) = profile(|| {
+            let mut i = "(((y".char_indices();
+            inner(&mut i)
+        });
+
+        assert_eq!( report.rule("nested").unwrap().calls, 4 );
+        assert_eq!( report.rule("nested").unwrap().fatals, 3 );
+        assert_eq!( report.rule("inner").unwrap().calls, 4 );
+        assert_eq!( report.rule("leaf").unwrap().errors, 1 );
+    }
+
+    #[test]
+    fn profile_should_count_pred_and_group_matchers() {
+        pred!(letter<'a> : char => char = |c : char| c.is_alphabetic());
+        group!(word<'a> : char => char = |input| { letter(input) });
+
+        let (_, report) = profile(|| {
+            ["a", "1"].iter().map(|s| word(&mut s.char_indices()).is_ok()).collect::<Vec<_>>()
+        });
+
+        assert_eq!( report.rule("word").map(|r| (r.calls, r.successes, r.errors)), Some((2, 1, 1)) );
+        assert_eq!( report.rule("letter").map(|r| (r.calls, r.successes, r.errors)), Some((2, 1, 1)) );
+    }
+
+    #[test]
+    fn profile_should_count_rescans_in_binary_matchers() {
+        let v : Vec<u8> = vec![0x01, 0x02, 0x03];
+        let (o, report) = profile(|| number::be_u32(&mut SliceInput::new(&v)));
+
+        assert!( o.is_err() );
+        assert_eq!( report.rule("be_u32").map(|r| (r.calls, r.fatals, r.rescanned)), Some((1, 1, 3)) );
+    }
+
+    #[test]
+    fn profile_should_restore_collector_after_panic() {
+        let (_, report) = profile(|| {
+            let o = std::panic::catch_unwind(|| profile(|| -> () { panic!("matcher panicked") }));
+            assert!( o.is_err() );
+            op(&mut "5".char_indices())
+        });
+
+        assert_eq!( report.rule("op").map(|r| r.calls), Some(1) );
+    }
+
+    #[test]
+    fn profile_should_export_csv_and_json() {
+        let (_, report) = profile(|| op(&mut "9".char_indices()));
+        let mut report = Profile { rules: report.rules.into_iter().map(|r| RuleProfile { time: std::time::Duration::from_micros(5), ..r }).collect() };
+        report.rules.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!( report.to_csv(), "rule,calls,successes,errors,fatals,rescanned,time_us\ndigit,1,1,0,0,0,5\nminus,1,0,1,0,1,5\nop,1,1,0,0,0,5\nplus,1,0,1,0,1,5\n" );
+        assert!( report.to_json().starts_with("[{\"rule\":\"digit\",\"calls\":1,\"successes\":1,\"errors\":0,\"fatals\":0,\"rescanned\":0,\"time_us\":5}," ) );
+        assert_eq!( report.to_string().lines().nth(1), Some("digit          1          1          0          0          0          5µs") );
+    }
+}
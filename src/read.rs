@@ -0,0 +1,236 @@
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::io::{ErrorKind, Read};
+use std::rc::Rc;
+
+use crate::{MatchError, MatchInput};
+
+const CHUNK_SIZE : usize = 8192;
+
+type LiveCheckpoints = Rc<RefCell<BTreeMap<usize, usize>>>;
+
+#[derive(Debug)]
+pub struct ReadCheckpoint {
+    position : usize,
+    live : LiveCheckpoints,
+}
+
+impl Drop for ReadCheckpoint {
+    fn drop(&mut self) {
+        let mut live = self.live.borrow_mut();
+        if let Some(count) = live.get_mut(&self.position) {
+            *count -= 1;
+            if *count == 0 {
+                live.remove(&self.position);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ReadInput<R> {
+    reader : R,
+    buffer : VecDeque<u8>,
+    offset : usize,
+    position : usize,
+    live : LiveCheckpoints,
+    error : Option<std::io::Error>,
+    eof : bool,
+}
+
+impl<R : Read> ReadInput<R> {
+    pub fn new(reader : R) -> Self {
+        ReadInput {
+            reader,
+            buffer: VecDeque::new(),
+            offset: 0,
+            position: 0,
+            live: Rc::new(RefCell::new(BTreeMap::new())),
+            error: None,
+            eof: false,
+        }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn fill(&mut self) -> bool {
+        let mut chunk = [0u8; CHUNK_SIZE];
+        loop {
+            match self.reader.read(&mut chunk) {
+                Ok(0) => {
+                    self.eof = true;
+                    return false;
+                },
+                Ok(n) => {
+                    self.buffer.extend(&chunk[..n]);
+                    return true;
+                },
+                Err(e) if e.kind() == ErrorKind::Interrupted => { },
+                Err(e) => {
+                    self.error = Some(e);
+                    return false;
+                },
+            }
+        }
+    }
+
+    fn trim(&mut self) {
+        let oldest = match self.live.borrow().keys().next() {
+            Some(&p) if p < self.position => p,
+            _ => self.position,
+        };
+        if oldest > self.offset {
+            self.buffer.drain(..oldest - self.offset);
+            self.offset = oldest;
+        }
+    }
+}
+
+impl<R : Read> MatchInput for ReadInput<R> {
+    type Item = u8;
+    type Checkpoint = ReadCheckpoint;
+
+    fn next_item(&mut self) -> Option<(usize, u8)> {
+        while self.position - self.offset >= self.buffer.len() {
+            if self.eof || self.error.is_some() || !self.fill() {
+                return None;
+            }
+        }
+        let item = self.buffer[self.position - self.offset];
+        let i = self.position;
+        self.position += 1;
+        self.trim();
+        Some((i, item))
+    }
+
+    fn checkpoint(&self) -> ReadCheckpoint {
+        *self.live.borrow_mut().entry(self.position).or_insert(0) += 1;
+        ReadCheckpoint { position: self.position, live: Rc::clone(&self.live) }
+    }
+
+    fn restore(&mut self, checkpoint : ReadCheckpoint) {
+        assert!(checkpoint.position >= self.offset, "checkpoint {} is no longer buffered", checkpoint.position);
+        self.position = checkpoint.position;
+        drop(checkpoint);
+        self.trim();
+    }
+
+    fn end_of_input(&mut self, fatal : bool) -> MatchError {
+        match self.error.take() {
+            Some(e) => MatchError::Io(e),
+            None if fatal => MatchError::FatalEndOfFile,
+            None => MatchError::ErrorEndOfFile,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    struct Trickle<'a> {
+        data : &'a [u8],
+        fail_at : Option<usize>,
+        index : usize,
+    }
+
+    impl<'a> Read for Trickle<'a> {
+        fn read(&mut self, buf : &mut [u8]) -> std::io::Result<usize> {
+            if Some(self.index) == self.fail_at {
+                return Err(std::io::Error::other("trickle failure"));
+            }
+            if self.index >= self.data.len() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.data[self.index];
+            self.index += 1;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn read_input_should_backtrack_within_window() -> Result<(), MatchError> {
+        seq!(a<'a>: u8 => u8 = _a <= 0x00, _b <= 0x01, c <= 0x02, { c });
+        seq!(b<'a>: u8 => u8 = _a <= 0x00, _b <= 0x01, c <= 0x03, { c });
+
+        let v : Vec<u8> = vec![0x00, 0x01, 0x03, 0x00, 0x01, 0x02];
+        let mut i = ReadInput::new(Trickle { data: &v, fail_at: None, index: 0 });
+
+        let failure = a(&mut i);
+
+        assert!( matches!( failure, Err(MatchError::Fatal(2)) ) );
+
+        let first = b(&mut i)?;
+        let second = a(&mut i)?;
+
+        assert_eq!( first.item, 0x03 );
+        assert_eq!( second.item, 0x02 );
+        assert_eq!( second.start, 3 );
+        assert_eq!( second.end, 5 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_input_should_only_buffer_back_to_oldest_checkpoint() -> Result<(), MatchError> {
+        seq!(zero_or_more ~ all<'a>: u8 => u8 = a <= _, { a });
+
+        let v : Vec<u8> = (0..=255).collect();
+        let mut i = ReadInput::new(Trickle { data: &v, fail_at: None, index: 0 });
+
+        let cp = i.checkpoint();
+        i.next_item();
+        i.next_item();
+
+        assert_eq!( i.buffered(), 2 );
+
+        drop(cp);
+        let o = all(&mut i)?;
+
+        assert_eq!( o.item.len(), 254 );
+        assert_eq!( o.start, 2 );
+        assert_eq!( o.end, 255 );
+        assert_eq!( i.buffered(), 0 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn read_input_should_report_io_errors() {
+        seq!(main<'a>: u8 => () = _a <= 0x00, _b <= 0x01, { () });
+
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = ReadInput::new(Trickle { data: &v, fail_at: Some(1), index: 0 });
+
+        let o = main(&mut i);
+
+        assert!( matches!( o, Err(MatchError::Io(_)) ) );
+        assert_eq!( i.position(), 0 );
+    }
+
+    #[test]
+    fn partial_read_input_should_report_io_errors() {
+        seq!(main<'a>: u8 => () = _a <= 0x00, _b <= 0x01, { () });
+
+        let v : Vec<u8> = vec![0x00, 0x01];
+        let mut i = Partial::new(ReadInput::new(Trickle { data: &v, fail_at: Some(1), index: 0 }));
+
+        assert!( matches!( main(&mut i), Err(MatchError::Io(_)) ) );
+
+        let mut i = Partial::new(ReadInput::new(Trickle { data: &v[..1], fail_at: None, index: 0 }));
+
+        assert!( matches!( main(&mut i), Err(MatchError::Incomplete(Some(1))) ) );
+    }
+}
@@ -0,0 +1,211 @@
+
+use futures::io::{AsyncRead, AsyncReadExt};
+use futures::stream::{self, Stream, StreamExt};
+
+use crate::{MatchError, MatchInput, SliceInput, Success};
+
+const CHUNK_SIZE : usize = 8192;
+
+#[derive(Debug)]
+pub struct StreamInput<'a> {
+    input : SliceInput<'a, u8>,
+    partial : bool,
+}
+
+impl<'a> StreamInput<'a> {
+    pub fn position(&self) -> usize {
+        self.input.position()
+    }
+}
+
+impl<'a> MatchInput for StreamInput<'a> {
+    type Item = &'a u8;
+    type Checkpoint = usize;
+
+    fn next_item(&mut self) -> Option<(usize, &'a u8)> {
+        self.input.next_item()
+    }
+
+    fn checkpoint(&self) -> usize {
+        self.input.checkpoint()
+    }
+
+    fn restore(&mut self, checkpoint : usize) {
+        self.input.restore(checkpoint)
+    }
+
+    fn seek(&mut self, position : usize) -> bool {
+        self.input.seek(position)
+    }
+
+    fn can_seek(&self) -> bool {
+        true
+    }
+
+    fn is_partial(&self) -> bool {
+        self.partial
+    }
+}
+
+struct State<S, F> {
+    source : S,
+    matcher : F,
+    buffer : Vec<u8>,
+    offset : usize,
+    exhausted : bool,
+    done : bool,
+}
+
+impl<S, F> State<S, F> {
+    fn run<T>(&mut self) -> Result<Success<T>, MatchError>
+        where F : for<'a> FnMut(&mut StreamInput<'a>) -> Result<Success<T>, MatchError> {
+
+        let mut input = StreamInput { input: SliceInput::with_offset(&self.buffer, self.offset), partial: !self.exhausted };
+        let result = (self.matcher)(&mut input);
+        let consumed = input.position() - self.offset;
+
+        if result.is_ok() {
+            if consumed == 0 {
+                self.done = true;
+            }
+            self.buffer.drain(..consumed);
+            self.offset += consumed;
+        }
+        result
+    }
+}
+
+pub fn parse_stream<S, B, F, T>(source : S, matcher : F) -> impl Stream<Item = Result<Success<T>, MatchError>>
+    where S : Stream<Item = std::io::Result<B>> + Unpin,
+          B : AsRef<[u8]>,
+          F : for<'a> FnMut(&mut StreamInput<'a>) -> Result<Success<T>, MatchError> {
+
+    let state = State { source, matcher, buffer: vec![], offset: 0, exhausted: false, done: false };
+
+    stream::unfold(state, |mut state| async move {
+        loop {
+            if state.done || (state.exhausted && state.buffer.is_empty()) {
+                return None;
+            }
+
+            match state.run() {
+                Err(MatchError::Incomplete(_)) if !state.exhausted => {
+                    match state.source.next().await {
+                        Some(Ok(chunk)) => { state.buffer.extend_from_slice(chunk.as_ref()); },
+                        Some(Err(e)) => {
+                            state.done = true;
+                            return Some((Err(MatchError::Io(e)), state));
+                        },
+                        None => { state.exhausted = true; },
+                    }
+                },
+                Err(e) => {
+                    state.done = true;
+                    return Some((Err(e), state));
+                },
+                Ok(success) => {
+                    return Some((Ok(success), state));
+                },
+            }
+        }
+    })
+}
+
+pub fn parse_reader<R, F, T>(reader : R, matcher : F) -> impl Stream<Item = Result<Success<T>, MatchError>>
+    where R : AsyncRead + Unpin,
+          F : for<'a> FnMut(&mut StreamInput<'a>) -> Result<Success<T>, MatchError> {
+
+    let chunks = stream::unfold((reader, false), |(mut reader, finished)| async move {
+        if finished {
+            return None;
+        }
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+        match reader.read(&mut chunk).await {
+            Ok(0) => None,
+            Ok(n) => {
+                chunk.truncate(n);
+                Some((Ok(chunk), (reader, false)))
+            },
+            Err(e) => Some((Err(e), (reader, true))),
+        }
+    });
+
+    parse_stream(Box::pin(chunks), matcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+    use futures::executor::block_on;
+
+    seq!(record<'a>: &'a u8 => Vec<u8> = _len <= _, body <= body, {
+        body
+    });
+
+    group!(body<'a>: &'a u8 => Vec<u8> = |input| {
+        seq!(zero_or_more ~ letters<'a>: &'a u8 => u8 = l <= b'a'..=b'z', { *l });
+        seq!(main<'a>: &'a u8 => Vec<u8> = l <= letters, _end <= b';', { l });
+        main(input)
+    });
+
+    fn chunks(data : &[&str]) -> impl Stream<Item = std::io::Result<Vec<u8>>> + Unpin {
+        let data : Vec<std::io::Result<Vec<u8>>> = data.iter().map(|d| Ok(d.as_bytes().to_vec())).collect();
+        futures::stream::iter(data)
+    }
+
+    #[test]
+    fn parse_stream_should_wait_for_records_split_across_chunks() {
+        let source = chunks(&["1ab", "c;2", "de;", "3f;"]);
+
+        let records : Vec<_> = block_on(parse_stream(source, |i| record(i)).collect());
+
+        assert_eq!( records.len(), 3 );
+        assert!( matches!( &records[0], Ok(Success { item, start: 0, end: 4 }) if item == b"abc" ) );
+        assert!( matches!( &records[1], Ok(Success { item, start: 5, end: 8 }) if item == b"de" ) );
+        assert!( matches!( &records[2], Ok(Success { item, start: 9, end: 11 }) if item == b"f" ) );
+    }
+
+    #[test]
+    fn parse_stream_should_report_truncated_final_record() {
+        let source = chunks(&["1ab;", "2c"]);
+
+        let records : Vec<_> = block_on(parse_stream(source, |i| record(i)).collect());
+
+        assert_eq!( records.len(), 2 );
+        assert!( records[0].is_ok() );
+        assert!( matches!( records[1], Err(MatchError::FatalEndOfFile) ) );
+    }
+
+    #[test]
+    fn parse_stream_should_stop_on_malformed_record() {
+        let source = chunks(&["1a;2", "b!;3c;"]);
+
+        let records : Vec<_> = block_on(parse_stream(source, |i| record(i)).collect());
+
+        assert_eq!( records.len(), 2 );
+        assert!( matches!( records[1], Err(MatchError::Fatal(5)) ) );
+    }
+
+    #[test]
+    fn parse_reader_should_parse_async_reader() {
+        let reader = futures::io::Cursor::new(b"1xy;2z;".to_vec());
+
+        let records : Vec<_> = block_on(parse_reader(reader, |i| record(i)).collect());
+
+        assert_eq!( records.len(), 2 );
+        assert!( matches!( &records[1], Ok(Success { item, start: 4, end: 6 }) if item == b"z" ) );
+    }
+
+    #[test]
+    fn parse_stream_should_stop_when_matcher_does_not_consume() {
+        seq!(zero_or_more ~ letters<'a>: &'a u8 => u8 = l <= b'a'..=b'z', { *l });
+
+        let source = chunks(&["123", "456"]);
+
+        let records : Vec<_> = block_on(parse_stream(source, |i| letters(i)).take(50).collect());
+
+        assert_eq!( records.len(), 1 );
+        assert!( matches!( &records[0], Ok(Success { item, .. }) if item.is_empty() ) );
+    }
+}
@@ -0,0 +1,175 @@
+
+pub use array_pattern_derive::{alt, seq};
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use crate::syntax::{alt, seq};
+
+    #[test]
+    fn seq_should_accept_macro_rules_syntax() -> Result<(), MatchError> {
+        pred!(space<'a> : char => char = |c : char| c.is_whitespace());
+        seq!(zero_or_more ~ ws<'a> : char => char = c <= space, { c });
+        seq!(zero_or_more ~ digits<'a> : char => char = d <= '0'..='9', { d });
+        seq!(number<'a> : char => u32 = d <= '0'..='9', rest <= digits, {
+            std::iter::once(d).chain(rest).collect::<String>().parse().unwrap()
+        });
+        seq!(plus<'a> : char => () = _p <= '+', { () });
+        seq!(minus<'a> : char => () = _m <= '-', { () });
+        alt!(skip(ws) ~ op<'a> : char => () = plus | minus);
+        seq!(maybe ~ sign<'a> : char => char = s <= '-', { s });
+        seq!(skip(ws) ~ expr<'a> : char => (Option<char>, u32, u32) = s <= sign, a <= number, _o <= op, b <= number, { (s, a, b) });
+
+        let v = "-12 +  3";
+        let mut i = v.char_indices();
+
+        let o = expr(&mut i)?;
+
+        assert_eq!( o.item, (Some('-'), 12, 3) );
+        assert_eq!( o.start, 0 );
+        assert_eq!( o.end, 7 );
+
+        let v = "1 * 3";
+        let mut i = v.char_indices();
+
+        assert!( matches!( expr(&mut i), Err(MatchError::Fatal(2)) ) );
+
+        Ok(())
+    }
+
+    #[test]
+    fn zero_or_more_should_stop_when_body_does_not_consume() -> Result<(), MatchError> {
+        seq!(maybe ~ sign<'a> : char => char = s <= '-', { s });
+        seq!(zero_or_more ~ signs<'a> : char => Option<char> = s <= sign, { s });
+
+        let v = "--x";
+        let mut i = v.char_indices();
+
+        let o = signs(&mut i)?;
+
+        assert_eq!( o.item, vec![Some('-'), Some('-')] );
+        assert_eq!( i.next(), Some((2, 'x')) );
+
+        let v = "";
+        let mut i = v.char_indices();
+
+        assert_eq!( signs(&mut i)?.item.len(), 0 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn seq_should_handle_bool_and_path_patterns() -> Result<(), MatchError> {
+        #[derive(Debug, PartialEq)]
+        enum Token {
+            A,
+            B,
+        }
+
+        seq!(flags<'a> : bool => (bool, bool) = a <= true, b <= false, { (a, b) });
+        seq!(tokens<'a> : &'a Token => () = _a <= Token::A, _b <= Token::B, { () });
+
+        let v = [true, false];
+        let mut i = v.into_iter().enumerate();
+
+        assert_eq!( flags(&mut i)?.item, (true, false) );
+
+        let v = [Token::A, Token::B];
+        let mut i = v.iter().enumerate();
+
+        assert_eq!( tokens(&mut i)?.end, 1 );
+
+        Ok(())
+    }
+
+    #[test]
+    fn seq_should_support_long_sequences() -> Result<(), MatchError> {
+        seq!(long<'a> : u8 => u8 =
+            a000 <= _, a001 <= _, a002 <= _, a003 <= _, a004 <= _, a005 <= _, a006 <= _, a007 <= _, a008 <= _, a009 <= _,
+            a010 <= _, a011 <= _, a012 <= _, a013 <= _, a014 <= _, a015 <= _, a016 <= _, a017 <= _, a018 <= _, a019 <= _,
+            a020 <= _, a021 <= _, a022 <= _, a023 <= _, a024 <= _, a025 <= _, a026 <= _, a027 <= _, a028 <= _, a029 <= _,
+            a030 <= _, a031 <= _, a032 <= _, a033 <= _, a034 <= _, a035 <= _, a036 <= _, a037 <= _, a038 <= _, a039 <= _,
+            a040 <= _, a041 <= _, a042 <= _, a043 <= _, a044 <= _, a045 <= _, a046 <= _, a047 <= _, a048 <= _, a049 <= _,
+            a050 <= _, a051 <= _, a052 <= _, a053 <= _, a054 <= _, a055 <= _, a056 <= _, a057 <= _, a058 <= _, a059 <= _,
+            a060 <= _, a061 <= _, a062 <= _, a063 <= _, a064 <= _, a065 <= _, a066 <= _, a067 <= _, a068 <= _, a069 <= _,
+            a070 <= _, a071 <= _, a072 <= _, a073 <= _, a074 <= _, a075 <= _, a076 <= _, a077 <= _, a078 <= _, a079 <= _,
+            a080 <= _, a081 <= _, a082 <= _, a083 <= _, a084 <= _, a085 <= _, a086 <= _, a087 <= _, a088 <= _, a089 <= _,
+            a090 <= _, a091 <= _, a092 <= _, a093 <= _, a094 <= _, a095 <= _, a096 <= _, a097 <= _, a098 <= _, a099 <= _,
+            a100 <= _, a101 <= _, a102 <= _, a103 <= _, a104 <= _, a105 <= _, a106 <= _, a107 <= _, a108 <= _, a109 <= _,
+            a110 <= _, a111 <= _, a112 <= _, a113 <= _, a114 <= _, a115 <= _, a116 <= _, a117 <= _, a118 <= _, a119 <= _,
+            a120 <= _, a121 <= _, a122 <= _, a123 <= _, a124 <= _, a125 <= _, a126 <= _, a127 <= _, a128 <= _, a129 <= _,
+            a130 <= _, a131 <= _, a132 <= _, a133 <= _, a134 <= _, a135 <= _, a136 <= _, a137 <= _, a138 <= _, a139 <= _,
+            a140 <= _, a141 <= _, a142 <= _, a143 <= _, a144 <= _, a145 <= _, a146 <= _, a147 <= _, a148 <= _, a149 <= _,
+            {
+                [a000, a001, a002, a003, a004, a005, a006, a007, a008, a009,
+                 a010, a011, a012, a013, a014, a015, a016, a017, a018, a019,
+                 a020, a021, a022, a023, a024, a025, a026, a027, a028, a029,
+                 a030, a031, a032, a033, a034, a035, a036, a037, a038, a039,
+                 a040, a041, a042, a043, a044, a045, a046, a047, a048, a049,
+                 a050, a051, a052, a053, a054, a055, a056, a057, a058, a059,
+                 a060, a061, a062, a063, a064, a065, a066, a067, a068, a069,
+                 a070, a071, a072, a073, a074, a075, a076, a077, a078, a079,
+                 a080, a081, a082, a083, a084, a085, a086, a087, a088, a089,
+                 a090, a091, a092, a093, a094, a095, a096, a097, a098, a099,
+                 a100, a101, a102, a103, a104, a105, a106, a107, a108, a109,
+                 a110, a111, a112, a113, a114, a115, a116, a117, a118, a119,
+                 a120, a121, a122, a123, a124, a125, a126, a127, a128, a129,
+                 a130, a131, a132, a133, a134, a135, a136, a137, a138, a139,
+                 a140, a141, a142, a143, a144, a145, a146, a147, a148, a149].iter().fold(0u8, |s, x| s.wrapping_add(*x))
+            });
+
+        let v : Vec<u8> = vec![1; 150];
+        let mut i = v.into_iter().enumerate();
+
+        let o = long(&mut i)?;
+
+        assert_eq!( o.item, 150 );
+        assert_eq!( o.end, 149 );
+
+        Ok(())
+    }
+
+    mod rules {
+        use crate::*;
+
+        pub fn parse(v : &str) -> String {
+            pred!(space<'a> : char => char = |c : char| c.is_whitespace());
+            seq!(zero_or_more ~ ws<'a> : char => char = c <= space, { c });
+            seq!(digit<'a> : char => char = d <= '0'..='9', { d });
+            seq!(zero_or_more ~ skip(ws) ~ digits<'a> : char => char = d <= digit, { d });
+            seq!(plus<'a> : char => char = p <= '+', { p });
+            seq!(minus<'a> : char => char = m <= '-', { m });
+            alt!(skip(ws) ~ op<'a> : char => char = plus | minus);
+            seq!(maybe ~ sign<'a> : char => char = s <= '-', { s });
+            seq!(skip(ws) ~ expr<'a> : char => (Option<char>, Vec<char>, char, Vec<char>) = s <= sign, a <= digits, o <= op, b <= digits, { (s, a, o, b) });
+
+            format!("{:?}", expr(&mut v.char_indices()))
+        }
+    }
+
+    mod derived {
+        use crate::*;
+        use crate::syntax::{alt, seq};
+
+        pub fn parse(v : &str) -> String {
+            pred!(space<'a> : char => char = |c : char| c.is_whitespace());
+            seq!(zero_or_more ~ ws<'a> : char => char = c <= space, { c });
+            seq!(digit<'a> : char => char = d <= '0'..='9', { d });
+            seq!(zero_or_more ~ skip(ws) ~ digits<'a> : char => char = d <= digit, { d });
+            seq!(plus<'a> : char => char = p <= '+', { p });
+            seq!(minus<'a> : char => char = m <= '-', { m });
+            alt!(skip(ws) ~ op<'a> : char => char = plus | minus);
+            seq!(maybe ~ sign<'a> : char => char = s <= '-', { s });
+            seq!(skip(ws) ~ expr<'a> : char => (Option<char>, Vec<char>, char, Vec<char>) = s <= sign, a <= digits, o <= op, b <= digits, { (s, a, o, b) });
+
+            format!("{:?}", expr(&mut v.char_indices()))
+        }
+    }
+
+    #[test]
+    fn derived_macros_should_match_macro_rules() {
+        for v in ["-1 2 + 3", "12-4", "+3", "1 *", "", "-", "7 +", " 1+1", "1 + 2 3 x"] {
+            assert_eq!( derived::parse(v), rules::parse(v), "input {:?}", v );
+        }
+    }
+}
@@ -0,0 +1,190 @@
+
+use std::cell::{Cell, RefCell};
+
+use crate::{MatchError, MatchInput, Success, TraceEvent};
+
+pub trait TraceSink {
+    fn event(&mut self, event : TraceEvent);
+}
+
+impl<F : FnMut(TraceEvent)> TraceSink for F {
+    fn event(&mut self, event : TraceEvent) {
+        self(event)
+    }
+}
+
+pub struct NoopSink;
+
+impl TraceSink for NoopSink {
+    fn event(&mut self, _event : TraceEvent) {
+    }
+}
+
+pub struct StderrSink;
+
+impl TraceSink for StderrSink {
+    fn event(&mut self, event : TraceEvent) {
+        eprintln!("{}", event);
+    }
+}
+
+thread_local! {
+    static SINK : RefCell<Box<dyn TraceSink>> = RefCell::new(Box::new(NoopSink));
+    static DEPTH : Cell<usize> = const { Cell::new(0) };
+}
+
+pub fn set_trace_sink(sink : Box<dyn TraceSink>) -> Box<dyn TraceSink> {
+    SINK.with(|s| s.replace(sink))
+}
+
+struct Restore {
+    sink : Option<Box<dyn TraceSink>>,
+    depth : usize,
+}
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        DEPTH.with(|d| d.set(self.depth));
+        if let Some(sink) = self.sink.take() {
+            set_trace_sink(sink);
+        }
+    }
+}
+
+pub fn capture_trace<R>(f : impl FnOnce() -> R) -> (R, Vec<TraceEvent>) {
+    let events = std::rc::Rc::new(RefCell::new(vec![]));
+    let captured = events.clone();
+    let previous = set_trace_sink(Box::new(move |e| captured.borrow_mut().push(e)));
+    let restore = Restore { sink: Some(previous), depth: DEPTH.with(|d| d.replace(0)) };
+    let ret = f();
+    drop(restore);
+    let events = events.take();
+    (ret, events)
+}
+
+fn emit(event : TraceEvent) {
+    SINK.with(|s| s.borrow_mut().event(event));
+}
+
+pub(crate) fn enter<I : MatchInput>(input : &mut I, matcher : &'static str) -> usize {
+    let depth = DEPTH.with(|d| d.replace(d.get() + 1));
+    emit(TraceEvent::Enter { depth, matcher: matcher.into(), position: crate::peek_position(input) });
+    depth
+}
+
+pub(crate) fn exit<T>(depth : usize, matcher : &'static str, result : &Result<Success<T>, MatchError>) {
+    DEPTH.with(|d| d.set(depth));
+    let result = match result {
+        Ok(s) => Ok((s.start, s.end)),
+        Err(e) => Err(format!("{:?}", e)),
+    };
+    emit(TraceEvent::Exit { depth, matcher: matcher.into(), result });
+}
+
+pub(crate) fn backtrack<I : MatchInput>(input : &mut I) {
+    let depth = DEPTH.with(|d| d.get());
+    emit(TraceEvent::Backtrack { depth, position: crate::peek_position(input) });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::cell::Cell;
+
+    seq!(zero_or_more ~ digits<'a> : char => char = d <= '0'..='9', { d });
+    seq!(plus<'a> : char => char = _p <= '+', d <= '0'..='9', { d });
+    seq!(minus<'a> : char => char = _m <= '-', d <= '0'..='9', { d });
+    alt!(op<'a> : char => char = plus | minus);
+
+    #[test]
+    fn trace_should_log_nested_enter_and_exit() {
+        let (o, events) = capture_trace(|| digits(&mut "12x".char_indices()));
+
+        assert_eq!( o.unwrap().end, 1 );
+        assert_eq!( events.first(), Some(&TraceEvent::Enter { depth: 0, matcher: "digits".into(), position: Some(0) }) );
+        assert_eq!( events.last(), Some(&TraceEvent::Exit { depth: 0, matcher: "digits".into(), result: Ok((0, 1)) }) );
+        assert!( events.contains(&TraceEvent::Backtrack { depth: 1, position: Some(2) }) );
+    }
+
+    #[test]
+    fn trace_should_show_alternatives_tried() {
+        let (o, events) = capture_trace(|| op(&mut "-2".char_indices()));
+
+        assert!( o.is_ok() );
+
+        let lines : Vec<String> = events.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!( lines, vec![
+            "op at 0",
+            "  plus at 0",
+            "    backtrack at 0",
+            "  plus failed: Error(0)",
+            "  minus at 0",
+            "  minus matched 0..=1",
+            "op matched 0..=1",
+        ] );
+    }
+
+    #[test]
+    fn trace_should_log_pred_and_group_matchers() {
+        pred!(letter<'a> : char => char = |c : char| c.is_alphabetic());
+        group!(word<'a> : char => char = |input| { letter(input) });
+
+        let (o, events) = capture_trace(|| word(&mut "x".char_indices()));
+
+        assert!( o.is_ok() );
+
+        let lines : Vec<String> = events.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!( lines, vec![
+            "word at 0",
+            "  letter at 0",
+            "  letter matched 0..=0",
+            "word matched 0..=0",
+        ] );
+    }
+
+    #[test]
+    fn trace_should_log_backtracks_in_binary_matchers() {
+        let v : Vec<u8> = vec![0x01];
+        let (o, events) = capture_trace(|| number::be_u16(&mut SliceInput::new(&v)));
+
+        assert!( o.is_err() );
+
+        let lines : Vec<String> = events.iter().map(|e| e.to_string()).collect();
+
+        assert_eq!( lines, vec![
+            "be_u16 at 0",
+            "  backtrack at 0",
+            "be_u16 failed: FatalEndOfFile",
+        ] );
+    }
+
+    #[test]
+    fn capture_trace_should_restore_sink_after_panic() {
+        let count = std::rc::Rc::new(Cell::new(0));
+        let c = count.clone();
+        let previous = set_trace_sink(Box::new(move |_e : TraceEvent| c.set(c.get() + 1)));
+
+        let o = std::panic::catch_unwind(|| capture_trace(|| -> () { panic!("matcher panicked") }));
+        let _ = op(&mut "+2".char_indices());
+
+        set_trace_sink(previous);
+
+        assert!( o.is_err() );
+        assert_eq!( count.get(), 4 );
+    }
+
+    #[test]
+    fn trace_sink_should_be_pluggable() {
+        let count = std::rc::Rc::new(Cell::new(0));
+        let c = count.clone();
+        let previous = set_trace_sink(Box::new(move |_e : TraceEvent| c.set(c.get() + 1)));
+
+        let _ = op(&mut "+2".char_indices());
+
+        set_trace_sink(previous);
+
+        assert_eq!( count.get(), 4 );
+    }
+}